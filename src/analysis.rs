@@ -0,0 +1,116 @@
+use std::collections::VecDeque;
+use crate::{
+    engine::*,
+    life_cell::*,
+};
+
+/// The longest period [`simulate`] recognises when running until stable.
+pub const MAX_PERIOD: usize = 1024;
+/// How many generations [`simulate`] runs when given no limit.
+pub const UNTIL_STABLE_LIMIT: u32 = 100_000;
+
+/// How a run through [`simulate`] ended.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Outcome {
+    /// The generation limit was reached (or stability wasn't being looked for).
+    Completed,
+    /// Every cell died.
+    DiedOut,
+    /// A generation left the world unchanged.
+    StillLife,
+    /// The world returned to an earlier state after the given period.
+    Oscillating(usize),
+}
+
+/// The state of a world at the end of [`simulate`].
+#[derive(Debug, Clone)]
+pub struct Summary {
+    /// The generation number reached.
+    pub generation: i32,
+    /// How many generations were actually run.
+    pub generations_run: u32,
+    /// Number of live cells.
+    pub population: usize,
+    /// Position and size of the live cells, `None` if there are none.
+    pub bounding_box: Option<((usize, usize), (usize, usize))>,
+    /// Why the run stopped.
+    pub outcome: Outcome,
+}
+
+impl Summary {
+    /// Formats the summary as "name: value" lines.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("generation: {}\n", self.generation);
+        text += &format!("generations run: {}\n", self.generations_run);
+        text += &format!("population: {}\n", self.population);
+        text += &match self.bounding_box {
+            Some(((x, y), (w, h))) => format!("bounding box: {}x{} at {},{}\n", w, h, x, y),
+            None => "bounding box: none\n".to_string(),
+        };
+        text += &match self.outcome {
+            Outcome::Completed => "result: still running\n".to_string(),
+            Outcome::DiedOut => "result: died out\n".to_string(),
+            Outcome::StillLife => "result: still life\n".to_string(),
+            Outcome::Oscillating(period) => format!("result: oscillating with period {}\n", period),
+        };
+        text
+    }
+}
+
+/// Runs `world` forward from `generation` for `limit` generations
+/// ([`UNTIL_STABLE_LIMIT`] if `None`), stopping short of that if the
+/// generation number would pass `i32::MAX`.
+///
+/// With `until_stable` the run stops early once the world dies out or
+/// repeats a state from the last [`MAX_PERIOD`] generations.
+pub fn simulate(world: &mut World, generation: i32, limit: Option<u32>, until_stable: bool) -> Summary {
+    simulate_with(world, generation, limit, until_stable, |_, _| true)
+}
+
+/// Like [`simulate`], but calls `observe` with the world and its generation
+/// number after every generation. The run stops early if it returns `false`.
+pub fn simulate_with<F>(world: &mut World, generation: i32, limit: Option<u32>, until_stable: bool, mut observe: F) -> Summary
+where
+    F: FnMut(&World, i32) -> bool,
+{
+    let limit = limit.unwrap_or(UNTIL_STABLE_LIMIT).min(i32::MAX.saturating_sub(generation).max(0) as u32);
+    let mut history = VecDeque::with_capacity(MAX_PERIOD);
+    let mut generations_run = 0;
+    let mut outcome = Outcome::Completed;
+    if until_stable {
+        history.push_back(world.state_hash());
+    }
+    while generations_run < limit {
+        if until_stable && world.live_cells().next().is_none() {
+            outcome = Outcome::DiedOut;
+            break;
+        }
+        world.update_world();
+        generations_run += 1;
+        if !observe(world, generation + generations_run as i32) {
+            break;
+        }
+        if until_stable {
+            let hash = world.state_hash();
+            if let Some(age) = history.iter().rev().position(|&h| h == hash) {
+                outcome = if age == 0 { Outcome::StillLife } else { Outcome::Oscillating(age + 1) };
+                break;
+            }
+            if history.len() == MAX_PERIOD {
+                history.pop_front();
+            }
+            history.push_back(hash);
+        }
+    }
+    if until_stable && outcome == Outcome::Completed && world.live_cells().next().is_none() {
+        outcome = Outcome::DiedOut;
+    }
+    Summary {
+        generation: generation + generations_run as i32,
+        generations_run,
+        population: world.live_cells().count(),
+        bounding_box: world.bounding_box(),
+        outcome,
+    }
+}
+
@@ -0,0 +1,156 @@
+use std::time::{
+    Duration,
+    Instant,
+};
+use crate::{
+    analysis,
+    engine::*,
+    life_cell::*,
+    pattern_library,
+    soup::*,
+};
+
+/// A standard starting position run for a number of generations.
+pub struct Workload {
+    /// Short name identifying the workload.
+    pub name: &'static str,
+    /// World size in cells (width, height).
+    pub world_size: (usize, usize),
+    /// Generations to run, or the most to run if `until_stable` is set.
+    pub generations: u32,
+    /// Whether to run only until the world stabilises, as found by
+    /// [`analysis::simulate`].
+    pub until_stable: bool,
+    setup: fn(&mut World),
+}
+
+impl Workload {
+    /// Builds the starting world.
+    pub fn world(&self) -> World {
+        let mut world = World::new(self.world_size);
+        (self.setup)(&mut world);
+        world
+    }
+
+    /// The number of generations every engine runs. For an `until_stable`
+    /// workload this runs the world once to find where it stabilises.
+    pub fn generations(&self) -> u32 {
+        if self.until_stable {
+            analysis::simulate(&mut self.world(), 0, Some(self.generations), true).generations_run
+        } else {
+            self.generations
+        }
+    }
+}
+
+fn place(world: &mut World, id: &str, pos: (usize, usize)) {
+    pattern_library::find(id).unwrap().pattern().place(world, pos);
+}
+
+/// The standard workloads.
+pub const WORKLOADS: &[Workload] = &[
+    Workload {
+        name: "soup",
+        world_size: (512, 512),
+        generations: 1000,
+        until_stable: false,
+        setup: |world| {
+            Soup::new(1, 0.5, Symmetry::C1).fill(world, ((0, 0), world.size()));
+        },
+    },
+    // on this torus the acorn's gliders wrap round into the debris, and it
+    // settles after about 10,000 generations rather than the 5206 it takes on
+    // an unbounded plane
+    Workload {
+        name: "acorn",
+        world_size: (512, 512),
+        generations: analysis::UNTIL_STABLE_LIMIT,
+        until_stable: true,
+        setup: |world| place(world, "acorn", (253, 254)),
+    },
+    // with the gun in the corner the first glider is still about 100 cells
+    // short of the far edge after 10,000 generations, so none wraps round
+    // and hits the gun
+    Workload {
+        name: "gosper-gun",
+        world_size: (2624, 2624),
+        generations: 10000,
+        until_stable: false,
+        setup: |world| place(world, "gosper-gun", (10, 10)),
+    },
+    // a few small objects far apart, so nearly every chunk is empty
+    Workload {
+        name: "sparse",
+        world_size: (2048, 2048),
+        generations: 100,
+        until_stable: false,
+        setup: |world| {
+            for (idx, id) in ["glider", "lwss", "r-pentomino", "pulsar", "glider", "acorn"].iter().enumerate() {
+                place(world, id, (150 + idx * 300, 200 + idx * 280));
+            }
+        },
+    },
+];
+
+/// Looks up a workload by name.
+pub fn find_workload(name: &str) -> Option<&'static Workload> {
+    WORKLOADS.iter().find(|workload| workload.name == name)
+}
+
+/// The timing of one workload on one engine.
+#[derive(Debug, Clone)]
+pub struct BenchmarkResult {
+    /// Name of the workload.
+    pub workload: &'static str,
+    /// Name of the engine.
+    pub engine: &'static str,
+    /// Cells in the world, all of which are updated every generation.
+    pub cells: usize,
+    /// Generations run.
+    pub generations: u32,
+    /// Time taken by the generations, not counting setup.
+    pub elapsed: Duration,
+    /// Population after the last generation.
+    pub population: usize,
+}
+
+impl BenchmarkResult {
+    /// Generations run per second.
+    pub fn generations_per_second(&self) -> f64 {
+        self.generations as f64 / self.elapsed.as_secs_f64().max(f64::MIN_POSITIVE)
+    }
+
+    /// Cells updated per second.
+    pub fn cell_updates_per_second(&self) -> f64 {
+        self.generations_per_second() * self.cells as f64
+    }
+
+    /// Formats the result as a single-line JSON object.
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"workload\":\"{}\",\"engine\":\"{}\",\"cells\":{},\"generations\":{},\"seconds\":{:.6},\
+            \"generations_per_second\":{:.3},\"cell_updates_per_second\":{:.0},\"population\":{}}}",
+            self.workload, self.engine, self.cells, self.generations, self.elapsed.as_secs_f64(),
+            self.generations_per_second(), self.cell_updates_per_second(), self.population,
+        )
+    }
+}
+
+/// Runs `workload` for `generations` (see [`Workload::generations`]) on the
+/// engine made by `builder` and times it.
+pub fn run(workload: &Workload, builder: EngineBuilder, generations: u32) -> BenchmarkResult {
+    let mut engine = builder(&workload.world());
+    let start = Instant::now();
+    for _ in 0..generations {
+        engine.step();
+    }
+    let elapsed = start.elapsed();
+    BenchmarkResult {
+        workload: workload.name,
+        engine: engine.name(),
+        cells: engine.size().0 * engine.size().1,
+        generations,
+        elapsed,
+        population: engine.population(),
+    }
+}
@@ -8,6 +8,11 @@ pub const BG_PATTERN_TBL: [Option<(u32, u32, &[u64])>; 256] = {
         tbl[0x20 + idx] = Some((1, 1, &BG_CHARS[idx]));
         idx += 1;
     }
+    let mut idx = 0;
+    while idx < TRAIL_CHARS.len() {
+        tbl[0x80 + idx] = Some((1, 1, &TRAIL_CHARS[idx]));
+        idx += 1;
+    }
     tbl
 };
 
@@ -974,3 +979,47 @@ pub const BG_CHARS: &[[u64; PATTERN_SIZE]] = &[
         0x0000000000000000,
     ],
 ];
+
+pub const TRAIL_CHARS: &[[u64; PATTERN_SIZE]] = &[
+    // 0x80 ~ 0x83(128 ~ 131)
+    [
+        0x0000000000000000,
+        0x0000000100000000,
+        0x0001010101010000,
+        0x0000010101000000,
+        0x0000010101000000,
+        0x0001000000010000,
+        0x0000000000000000,
+        0x0000000000000000,
+    ],
+    [
+        0x0000000000000000,
+        0x0000000100000000,
+        0x0001000100010000,
+        0x0000010001000000,
+        0x0000000100000000,
+        0x0001000000010000,
+        0x0000000000000000,
+        0x0000000000000000,
+    ],
+    [
+        0x0000000000000000,
+        0x0000000000000000,
+        0x0000000100000000,
+        0x0000010001000000,
+        0x0000000100000000,
+        0x0000000000000000,
+        0x0000000000000000,
+        0x0000000000000000,
+    ],
+    [
+        0x0000000000000000,
+        0x0000000000000000,
+        0x0000000000000000,
+        0x0000000100000000,
+        0x0000000000000000,
+        0x0000000000000000,
+        0x0000000000000000,
+        0x0000000000000000,
+    ],
+];
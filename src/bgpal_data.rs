@@ -1,5 +1,24 @@
 use bgsp_lib2::bgsp_common::{Rgba, NUM_PALETTE_TBL, NUM_PALETTE_COL};
 
+pub const STATE_PALETTE_BASE: usize = 16;
+
+// COLOR_TBL with palette STATE_PALETTE_BASE + n drawing state n in its rule file colour;
+// states without one get Golly's default, red to yellow
+pub fn color_tbl_with_states(num_states: usize, colors: &[(usize, (u8, u8, u8))]) -> Box<[[Rgba<u8>; NUM_PALETTE_COL]; NUM_PALETTE_TBL]> {
+    let mut tbl = Box::new(COLOR_TBL);
+    let steps = num_states.saturating_sub(2).max(1);
+    let defaults = (1..num_states).map(|state| (state, (255, ((state - 1) * 255 / steps) as u8, 0)));
+    for (state, (r, g, b)) in defaults.chain(colors.iter().copied()) {
+        if let Some(pal) = tbl.get_mut(STATE_PALETTE_BASE + state) {
+            pal[0] = Rgba([0, 0, 0, 0]);
+            pal[1] = Rgba([r, g, b, 255]);
+            pal[2] = Rgba([0, 0, 0, 255]);
+            pal[3] = Rgba([r, g, b, 255]);
+        }
+    }
+    tbl
+}
+
 pub const COLOR_TBL: [[Rgba<u8>; NUM_PALETTE_COL]; NUM_PALETTE_TBL] = {
     let mut tbl: [[Rgba<u8>; NUM_PALETTE_COL]; NUM_PALETTE_TBL] = [[Rgba([0, 0, 0, 0]); NUM_PALETTE_COL]; NUM_PALETTE_TBL];
     let mut tbl_no = 0;
@@ -22,6 +41,7 @@ const PALS: &[&[(u8, u8, u8, u8)]] = &[
     BG_PAL_2,
     BG_PAL_3,
     BG_PAL_4,
+    BG_PAL_5,
 ];
 
 const BG_PAL_1: &[(u8, u8, u8, u8)] = &[
@@ -51,3 +71,10 @@ const BG_PAL_4: &[(u8, u8, u8, u8)] = &[
     (  0,   0,   0, 255),
     (216, 216,   0, 255),
 ];
+
+const BG_PAL_5: &[(u8, u8, u8, u8)] = &[
+    (  0,   0,   0,   0),
+    (120, 120, 120, 255),
+    (  0,   0,   0, 255),
+    (120, 120, 120, 255),
+];
@@ -0,0 +1,605 @@
+use crate::{
+    benchmark::*,
+    engine::*,
+    life_cell::{
+        MAX_WORLD_SIZE,
+        MIN_WORLD_SIZE,
+    },
+    pattern::Format,
+    pattern_library::*,
+    soup::*,
+    stream::*,
+};
+use crate::direction::*;
+use crate::remote::*;
+#[cfg(feature = "viewer")]
+use bgsp_lib2::bgsp_common::PATTERN_SIZE;
+// nothing is drawn without the viewer, but the display options are still checked
+#[cfg(not(feature = "viewer"))]
+const PATTERN_SIZE: usize = 8;
+
+const WORLD_SIZE: (usize, usize) = (512, 512);
+const FULL_SCREEN: bool = false;
+const VM_RECT_SIZE: (i32, i32) = (120 * PATTERN_SIZE as i32, 120 * PATTERN_SIZE as i32);
+const ROTATION: Direction = Direction::Normal;
+const PIXEL_SCALE: i32 = 1;
+const WINDOW_MARGIN: i32 = 0;
+const TRAIL_LENGTH: i32 = 8;
+const TRAIL_GLYPHS: &[char] = &['\u{80}', '\u{81}', '\u{82}', '\u{83}'];
+
+const MAX_VM_RECT_SIZE: i32 = 4096;
+const MAX_PIXEL_SCALE: i32 = 16;
+const MAX_WINDOW_MARGIN: i32 = 1024;
+const MAX_TRAIL_LENGTH: i32 = 1000;
+
+/// The text printed by `--help`.
+pub const USAGE: &str = "\
+usage: life-rs [OPTIONS] [PATTERN]
+
+PATTERN is a pattern file (.rle, .cells, .lif, .life, .mc), the id of a
+built-in pattern, \"empty\", or random:<density>[:<seed>[:<symmetry>]].
+Without one the acorn is placed at 60,62.
+
+pattern options:
+    --place centre|X,Y      where to put the pattern (default: centre)
+    --rotate 0|90|180|270   rotate the pattern clockwise
+    --flip h|v              mirror the pattern (may be given twice)
+    --resume FILE           start from a saved session
+
+display options:
+    --full-screen           use the whole screen
+    --windowed              use a window (default)
+    --vm-size WxH           size of the virtual screen in pixels (default: 960x960)
+    --screen-rotation up|right|down|left
+                            rotate the whole display (default: up)
+    --scale N               pixel scale, 1 to 16 (default: 1)
+    --margin N              window margin in pixels, 0 to 1024 (default: 0)
+    --trail-length N        generations the trail of a dead cell lasts, 1 to
+                            1000 (default: 8)
+    --trail-glyphs LIST     what the trail fades through, as characters or hex
+                            codes separated by commas (default: 0x80,0x81,0x82,0x83)
+
+world options:
+    --world WxH             world size in cells (default: 512x512)
+    --rule FILE             run a Golly .rule file (@TABLE or @TREE, drawn in
+                            its @COLORS) instead of the pattern's rule; the
+                            pattern's cells start in state 1, and saving or
+                            exporting keeps only which cells are not in state 0
+
+batch options:
+    --headless              run without a window and print a summary
+    --generations N         number of generations to run
+    --until-stable          stop once the pattern dies out or repeats
+                            (within --generations, if given)
+    --output FILE           write the final pattern to FILE, in the format
+                            given by its extension (default: RLE to stdout,
+                            with the summary on stderr)
+    --stream diff|rle|cells|life105|life106|mc
+                            write generations to stdout as they are run,
+                            either as diff lines (\"GEN +x,y -x,y ...\" for
+                            births and deaths, after a \"#\" header and a
+                            first line holding every live cell) or as whole
+                            patterns in a file format; the summary goes to
+                            stderr
+    --every N               only stream every Nth generation (and the last)
+
+benchmark options:
+    --benchmark             time the standard workloads on every engine and
+                            print one JSON object per run (build with --release)
+    --engine NAME           only use this engine: world or state-grid
+                            (may be given more than once, also for --verify)
+    --workload NAME         only run this workload: soup, acorn, gosper-gun
+                            or sparse (may be given more than once)
+
+verification options:
+    --verify                run seeded random soups through the reference
+                            engine and every other engine side by side and
+                            report the first generation and cells where
+                            they disagree
+    --soups N               number of soups, seeded 1 to N (default: 4)
+    --generations N         generations per soup (default: 1000)
+    --world WxH             sets the soup size as well
+
+remote control options:
+    --listen PORT|HOST:PORT|unix:PATH
+                            accept commands on a localhost TCP port or a
+                            Unix-domain socket while the window is open
+
+    --help                  show this help
+
+Display defaults and key bindings can be set in life-rs/config.ini under
+$XDG_CONFIG_HOME (usually ~/.config), e.g.
+
+    [display]
+    world = 1024x1024
+    scale = 2
+
+    [keys]
+    Pause = P, Return
+    Quit = Escape, Q
+
+Display options take the names above without the dashes, plus
+full-screen = true|false. A role listed under [keys] loses its default keys.
+
+The remote control reads one JSON object per line and answers each with one
+line holding \"ok\", \"generation\", \"population\" and \"paused\", or \"ok\": false
+and an \"error\". An \"id\" member is echoed back. A line that isn't JSON closes
+the connection, and files must be relative paths under the working directory.
+Commands:
+
+    {\"command\": \"step\", \"generations\": 10}
+    {\"command\": \"pause\"}  {\"command\": \"run\"}
+    {\"command\": \"set_cells\", \"cells\": [[10, 20], [11, 20]], \"alive\": true}
+    {\"command\": \"load\", \"pattern\": \"glider\"}  (a file or built-in id)
+    {\"command\": \"load\", \"text\": \"x = 3, y = 1\\n3o!\"}
+    {\"command\": \"population\"}
+    {\"command\": \"region\", \"x\": 0, \"y\": 0, \"width\": 64, \"height\": 64}
+                            answers with the live \"cells\" in the region
+    {\"command\": \"view\", \"x\": 100, \"y\": 100}
+    {\"command\": \"goto\", \"generation\": 500}  {\"command\": \"clear\"}
+    {\"command\": \"random\", \"density\": 0.3, \"seed\": 42}  (\"seed\" is optional)
+    {\"command\": \"rule\", \"rule\": \"B36/S23\"}
+    {\"command\": \"save\", \"file\": \"world.mc\"}
+                            writes the world in the format of the extension
+                            (.rle, .cells, .lif or .mc)
+    {\"command\": \"speed\", \"speed\": 120}  {\"command\": \"help\"}
+    {\"command\": \"until\", \"condition\": \"population < 100\"}
+                            pause once the condition holds (also \"stable\",
+                            \"generation N\", \"change X Y WIDTH HEIGHT\" and
+                            \"clear\"); without one, lists the conditions
+";
+
+/// Where the starting pattern comes from.
+pub enum PatternSource {
+    /// A pattern file.
+    File(String),
+    /// A pattern from the built-in library.
+    Library(&'static LibraryEntry),
+    /// A random soup filling the world.
+    Random(Soup),
+    /// No pattern at all.
+    Empty,
+}
+
+/// Where the starting pattern goes in the world.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Placement {
+    /// In the middle.
+    Centre,
+    /// With its top-left corner at the given cell.
+    At(usize, usize),
+}
+
+/// The parsed command line; see [`USAGE`] for what each option does.
+pub struct Args {
+    /// The PATTERN argument.
+    pub pattern: PatternSource,
+    /// `--place`.
+    pub placement: Placement,
+    /// `--rotate`, in quarter turns clockwise.
+    pub rotation: u32,
+    /// `--flip h`.
+    pub flip_horizontal: bool,
+    /// `--flip v`.
+    pub flip_vertical: bool,
+    /// `--resume`.
+    pub resume: Option<String>,
+    /// `--full-screen` or `--windowed`.
+    pub full_screen: bool,
+    /// `--vm-size`, in pixels.
+    pub vm_rect_size: (i32, i32),
+    /// `--screen-rotation`.
+    pub screen_rotation: Direction,
+    /// `--scale`.
+    pub pixel_scale: i32,
+    /// `--margin`.
+    pub window_margin: i32,
+    /// `--trail-length`.
+    pub trail_length: i32,
+    /// `--trail-glyphs`.
+    pub trail_glyphs: Vec<char>,
+    /// `--world`, in cells.
+    pub world_size: (usize, usize),
+    /// `--rule`.
+    pub rule_file: Option<String>,
+    /// `--headless`.
+    pub headless: bool,
+    /// `--generations`.
+    pub generations: Option<u32>,
+    /// `--until-stable`.
+    pub until_stable: bool,
+    /// `--output`.
+    pub output: Option<String>,
+    /// `--stream`.
+    pub stream: Option<StreamFormat>,
+    /// `--every`.
+    pub every: Option<u32>,
+    /// `--benchmark`.
+    pub benchmark: bool,
+    /// Every `--engine`.
+    pub engines: Vec<String>,
+    /// Every `--workload`.
+    pub workloads: Vec<String>,
+    /// `--verify`.
+    pub verify: bool,
+    /// `--soups`.
+    pub soups: Option<u32>,
+    /// `--listen`.
+    pub listen: Option<ListenAddress>,
+    /// `--help`.
+    pub help: bool,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            pattern: PatternSource::Library(find("acorn").unwrap()),
+            placement: Placement::At(60, 62),
+            rotation: 0,
+            flip_horizontal: false,
+            flip_vertical: false,
+            resume: None,
+            full_screen: FULL_SCREEN,
+            vm_rect_size: VM_RECT_SIZE,
+            screen_rotation: ROTATION,
+            pixel_scale: PIXEL_SCALE,
+            window_margin: WINDOW_MARGIN,
+            trail_length: TRAIL_LENGTH,
+            trail_glyphs: TRAIL_GLYPHS.to_vec(),
+            world_size: WORLD_SIZE,
+            rule_file: None,
+            headless: false,
+            generations: None,
+            until_stable: false,
+            output: None,
+            stream: None,
+            every: None,
+            benchmark: false,
+            engines: Vec::new(),
+            workloads: Vec::new(),
+            verify: false,
+            soups: None,
+            listen: None,
+            help: false,
+        }
+    }
+}
+
+impl Args {
+    /// Parses the arguments after the program name, starting from `defaults`.
+    pub fn parse<I: Iterator<Item = String>>(mut args: I, defaults: Self) -> Result<Self, String> {
+        let mut parsed = defaults;
+        let mut placement = None;
+        let mut pattern = None;
+        while let Some(arg) = args.next() {
+            let mut value = |option: &str| args.next().ok_or_else(|| format!("{} needs a value", option));
+            match arg.as_str() {
+                "--place" => placement = Some(parse_placement(&value("--place")?)?),
+                "--rotate" => {
+                    let degrees = value("--rotate")?;
+                    parsed.rotation = match degrees.as_str() {
+                        "0" | "90" | "180" | "270" => degrees.parse::<u32>().unwrap() / 90,
+                        _ => return Err(format!("--rotate must be 0, 90, 180 or 270, not \"{}\"", degrees)),
+                    };
+                }
+                "--flip" => match value("--flip")?.as_str() {
+                    "h" | "horizontal" => parsed.flip_horizontal = true,
+                    "v" | "vertical" => parsed.flip_vertical = true,
+                    flip => return Err(format!("--flip must be h or v, not \"{}\"", flip)),
+                },
+                "--resume" => parsed.resume = Some(value("--resume")?),
+                "--full-screen" => parsed.full_screen = true,
+                "--windowed" => parsed.full_screen = false,
+                "--vm-size" | "--screen-rotation" | "--scale" | "--margin" | "--trail-length" | "--trail-glyphs" | "--world" => {
+                    let value = value(&arg)?;
+                    parsed.set_display_option(&arg[2..], &value).map_err(|e| format!("--{}", e))?;
+                }
+                "--headless" => parsed.headless = true,
+                "--generations" => {
+                    let generations = value("--generations")?;
+                    // generation numbers are i32 all the way through
+                    parsed.generations = Some(generations.parse().ok().filter(|&count| count <= i32::MAX as u32)
+                        .ok_or_else(|| format!("--generations must be a number up to {}, not \"{}\"", i32::MAX, generations))?);
+                }
+                "--until-stable" => parsed.until_stable = true,
+                "--output" => parsed.output = Some(value("--output")?),
+                "--stream" => parsed.stream = Some(match value("--stream")?.as_str() {
+                    "diff" => StreamFormat::Diff,
+                    "rle" => StreamFormat::Frame(Format::Rle),
+                    "cells" => StreamFormat::Frame(Format::Plaintext),
+                    "life105" => StreamFormat::Frame(Format::Life105),
+                    "life106" => StreamFormat::Frame(Format::Life106),
+                    "mc" => StreamFormat::Frame(Format::Macrocell),
+                    format => return Err(format!("--stream must be diff, rle, cells, life105, life106 or mc, not \"{}\"", format)),
+                }),
+                "--every" => {
+                    let every = value("--every")?;
+                    parsed.every = Some(every.parse().ok().filter(|&every| every > 0)
+                        .ok_or_else(|| format!("--every must be a positive number, not \"{}\"", every))?);
+                }
+                "--benchmark" => parsed.benchmark = true,
+                "--engine" => {
+                    let engine = value("--engine")?;
+                    if find_engine(&engine).is_none() {
+                        let names: Vec<_> = ENGINES.iter().map(|(name, _)| *name).collect();
+                        return Err(format!("unknown engine \"{}\" (available: {})", engine, names.join(", ")));
+                    }
+                    parsed.engines.push(engine);
+                }
+                "--workload" => {
+                    let workload = value("--workload")?;
+                    if find_workload(&workload).is_none() {
+                        let names: Vec<_> = WORKLOADS.iter().map(|workload| workload.name).collect();
+                        return Err(format!("unknown workload \"{}\" (available: {})", workload, names.join(", ")));
+                    }
+                    parsed.workloads.push(workload);
+                }
+                "--verify" => parsed.verify = true,
+                "--soups" => {
+                    let soups = value("--soups")?;
+                    parsed.soups = Some(soups.parse().ok().filter(|&soups| soups > 0)
+                        .ok_or_else(|| format!("--soups must be a positive number, not \"{}\"", soups))?);
+                }
+                "--rule" => parsed.rule_file = Some(value("--rule")?),
+                "--listen" => parsed.listen = Some(ListenAddress::parse(&value("--listen")?).map_err(|e| format!("--listen: {}", e))?),
+                "--help" | "-h" => parsed.help = true,
+                option if option.starts_with("--") => return Err(format!("unknown option {}", option)),
+                _ if pattern.is_some() => return Err(format!("more than one pattern given (\"{}\")", arg)),
+                _ => pattern = Some(parse_pattern_source(&arg)?),
+            }
+        }
+        if let Some(pattern) = pattern {
+            parsed.pattern = pattern;
+            parsed.placement = Placement::Centre;
+        }
+        if let Some(placement) = placement {
+            parsed.placement = placement;
+        }
+        if [parsed.headless, parsed.benchmark, parsed.verify].iter().filter(|&&mode| mode).count() > 1 {
+            return Err("only one of --headless, --benchmark and --verify can be used".to_string());
+        }
+        if (parsed.headless || parsed.benchmark || parsed.verify) && parsed.listen.is_some() {
+            return Err("--listen needs the window (not --headless, --benchmark or --verify)".to_string());
+        }
+        if (parsed.headless || parsed.benchmark || parsed.verify) && parsed.rule_file.is_some() {
+            return Err("--rule needs the window (not --headless, --benchmark or --verify)".to_string());
+        }
+        if !parsed.benchmark && !parsed.verify && !parsed.engines.is_empty() {
+            return Err("--engine needs --benchmark or --verify".to_string());
+        }
+        if !parsed.benchmark && !parsed.workloads.is_empty() {
+            return Err("--workload needs --benchmark".to_string());
+        }
+        if !parsed.verify && parsed.soups.is_some() {
+            return Err("--soups needs --verify".to_string());
+        }
+        if !parsed.headless && !parsed.verify && parsed.generations.is_some() {
+            return Err("--generations needs --headless or --verify".to_string());
+        }
+        if !parsed.headless && (parsed.until_stable || parsed.output.is_some() || parsed.stream.is_some()) {
+            return Err("--until-stable, --output and --stream need --headless".to_string());
+        }
+        if parsed.stream.is_none() && parsed.every.is_some() {
+            return Err("--every needs --stream".to_string());
+        }
+        if parsed.headless && parsed.generations.is_none() && !parsed.until_stable {
+            return Err("--headless needs --generations N or --until-stable".to_string());
+        }
+        if let Placement::At(x, y) = parsed.placement {
+            let (width, height) = parsed.world_size;
+            if x >= width || y >= height {
+                if placement.is_some() {
+                    return Err(format!("--place {},{} lies outside the {}x{} world", x, y, width, height));
+                }
+                parsed.placement = Placement::Centre;
+            }
+        }
+        Ok(parsed)
+    }
+
+    // shared by the command line and the [display] section of the config file;
+    // errors start with the option name so the caller can decorate it
+    /// Sets one of the display options by its name without the dashes, as
+    /// config.ini names them.
+    pub fn set_display_option(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "full-screen" => self.full_screen = match value {
+                "true" | "yes" | "on" => true,
+                "false" | "no" | "off" => false,
+                _ => return Err(format!("{} must be true or false, not \"{}\"", name, value)),
+            },
+            "vm-size" => {
+                let (width, height) = parse_size(name, value)?;
+                let limit = PATTERN_SIZE as i32..=MAX_VM_RECT_SIZE;
+                if !limit.contains(&width) || !limit.contains(&height) {
+                    return Err(format!("{0} must be between {1}x{1} and {2}x{2} pixels", name, limit.start(), limit.end()));
+                }
+                self.vm_rect_size = (width, height);
+            }
+            "screen-rotation" => self.screen_rotation = match value {
+                "up" | "normal" => Direction::Up,
+                "right" => Direction::Right,
+                "down" => Direction::Down,
+                "left" => Direction::Left,
+                _ => return Err(format!("{} must be up, right, down or left, not \"{}\"", name, value)),
+            },
+            "scale" => self.pixel_scale = parse_in_range(name, value, 1, MAX_PIXEL_SCALE)?,
+            "margin" => self.window_margin = parse_in_range(name, value, 0, MAX_WINDOW_MARGIN)?,
+            "trail-length" => self.trail_length = parse_in_range(name, value, 1, MAX_TRAIL_LENGTH)?,
+            "trail-glyphs" => self.trail_glyphs = parse_glyphs(name, value)?,
+            "world" => {
+                let (width, height) = parse_size(name, value)?;
+                let min = MIN_WORLD_SIZE;
+                if width < min.0 || height < min.1 || width > MAX_WORLD_SIZE || height > MAX_WORLD_SIZE {
+                    return Err(format!("{} must be between {}x{} and {3}x{3} cells", name, min.0, min.1, MAX_WORLD_SIZE));
+                }
+                self.world_size = (width, height);
+            }
+            _ => return Err(format!("{} is not a display option", name)),
+        }
+        Ok(())
+    }
+}
+
+fn parse_size<T: std::str::FromStr>(option: &str, text: &str) -> Result<(T, T), String> {
+    match text.split_once(['x', 'X']).map(|(width, height)| (width.trim().parse(), height.trim().parse())) {
+        Some((Ok(width), Ok(height))) => Ok((width, height)),
+        _ => Err(format!("{} must be WIDTHxHEIGHT, not \"{}\"", option, text)),
+    }
+}
+
+fn parse_in_range(option: &str, text: &str, min: i32, max: i32) -> Result<i32, String> {
+    text.parse::<i32>().ok()
+        .filter(|n| (min..=max).contains(n))
+        .ok_or_else(|| format!("{} must be a number from {} to {}, not \"{}\"", option, min, max, text))
+}
+
+// "a,b,c" or "0x80,0x81"; the font has 256 glyphs
+fn parse_glyphs(option: &str, text: &str) -> Result<Vec<char>, String> {
+    text.split(',').map(str::trim).map(|glyph| {
+        let mut chars = glyph.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => glyph.strip_prefix("0x").and_then(|hex| u32::from_str_radix(hex, 16).ok()).and_then(char::from_u32),
+        }
+        .filter(|&c| (c as u32) < 0x100)
+        .ok_or_else(|| format!("{} must be characters or hex codes below 0x100 separated by commas, not \"{}\"", option, glyph))
+    }).collect()
+}
+
+fn parse_placement(text: &str) -> Result<Placement, String> {
+    if text == "centre" || text == "center" {
+        return Ok(Placement::Centre);
+    }
+    match text.split_once(',').map(|(x, y)| (x.trim().parse(), y.trim().parse())) {
+        Some((Ok(x), Ok(y))) => Ok(Placement::At(x, y)),
+        _ => Err(format!("--place must be \"centre\" or \"X,Y\", not \"{}\"", text)),
+    }
+}
+
+fn parse_pattern_source(text: &str) -> Result<PatternSource, String> {
+    if text == "empty" {
+        return Ok(PatternSource::Empty);
+    }
+    if let Some(spec) = text.strip_prefix("random:") {
+        let fields: Vec<_> = spec.split(':').collect();
+        let density = fields[0].parse::<f64>().ok()
+            .filter(|density| (0.0..=1.0).contains(density))
+            .ok_or_else(|| format!("random density must be between 0 and 1, not \"{}\"", fields[0]))?;
+        let seed = match fields.get(1) {
+            Some(seed) => seed.parse::<u64>().map_err(|_| format!("invalid random seed \"{}\"", seed))?,
+            None => std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_nanos() as u64),
+        };
+        let symmetry = match fields.get(2) {
+            Some(name) => Symmetry::from_name(name).ok_or_else(|| format!("unknown symmetry \"{}\"", name))?,
+            None => Symmetry::default(),
+        };
+        if fields.len() > 3 {
+            return Err(format!("expected random:<density>[:<seed>[:<symmetry>]], not \"{}\"", text));
+        }
+        return Ok(PatternSource::Random(Soup::new(seed, density, symmetry)));
+    }
+    if !std::path::Path::new(text).exists() {
+        if let Some(entry) = find(text) {
+            return Ok(PatternSource::Library(entry));
+        }
+    }
+    Ok(PatternSource::File(text.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|arg| arg.to_string()), Args::default())
+    }
+
+    fn error(args: &[&str]) -> String {
+        match parse(args) {
+            Ok(_) => panic!("{:?} should not parse", args),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn defaults_to_the_acorn() {
+        let args = parse(&[]).unwrap();
+        assert!(matches!(args.pattern, PatternSource::Library(entry) if entry.id == "acorn"));
+        assert_eq!(args.placement, Placement::At(60, 62));
+        assert_eq!(args.world_size, WORLD_SIZE);
+    }
+
+    #[test]
+    fn parses_patterns_and_placement() {
+        let args = parse(&["glider"]).unwrap();
+        assert!(matches!(args.pattern, PatternSource::Library(entry) if entry.id == "glider"));
+        assert_eq!(args.placement, Placement::Centre);
+
+        let args = parse(&["--place", "10,20", "--rotate", "270", "--flip", "v", "some file.rle"]).unwrap();
+        assert!(matches!(args.pattern, PatternSource::File(ref path) if path == "some file.rle"));
+        assert_eq!(args.placement, Placement::At(10, 20));
+        assert_eq!(args.rotation, 3);
+        assert!(args.flip_vertical && !args.flip_horizontal);
+
+        let args = parse(&["random:0.25:42:D2"]).unwrap();
+        assert!(matches!(args.pattern, PatternSource::Random(soup) if soup.seed == 42 && soup.density == 0.25));
+        assert!(matches!(parse(&["empty"]).unwrap().pattern, PatternSource::Empty));
+    }
+
+    #[test]
+    fn parses_display_options() {
+        let args = parse(&["--world", "64X32", "--scale", "3", "--screen-rotation", "left", "--full-screen",
+            "--trail-length", "20", "--trail-glyphs", "#,0x81"]).unwrap();
+        assert_eq!(args.world_size, (64, 32));
+        assert_eq!(args.pixel_scale, 3);
+        assert_eq!(args.screen_rotation, Direction::Left);
+        assert!(args.full_screen);
+        assert_eq!(args.trail_length, 20);
+        assert_eq!(args.trail_glyphs, ['#', '\u{81}']);
+    }
+
+    #[test]
+    fn starts_from_the_given_defaults() {
+        let mut defaults = Args::default();
+        defaults.set_display_option("scale", "2").unwrap();
+        defaults.set_display_option("world", "100x100").unwrap();
+        let args = Args::parse(["--world", "200x200"].into_iter().map(String::from), defaults).unwrap();
+        assert_eq!(args.pixel_scale, 2);
+        assert_eq!(args.world_size, (200, 200));
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        assert_eq!(error(&["--scale"]), "--scale needs a value");
+        assert!(error(&["--scale", "0"]).starts_with("--scale must be a number"));
+        assert!(error(&["--world", "8x8"]).starts_with("--world must be between"));
+        assert!(error(&["--world", "64"]).starts_with("--world must be WIDTHxHEIGHT"));
+        assert!(error(&["--trail-glyphs", "0x100"]).starts_with("--trail-glyphs must be characters"));
+        assert!(error(&["--trail-glyphs", "ab"]).starts_with("--trail-glyphs must be characters"));
+        assert!(error(&["--rotate", "45"]).starts_with("--rotate must be"));
+        assert!(error(&["--place", "here"]).starts_with("--place must be"));
+        assert!(error(&["--place", "600,0"]).contains("lies outside"));
+        assert!(error(&["random:2"]).starts_with("random density"));
+        assert!(error(&["random:0.5:1:D2:x"]).starts_with("expected random:"));
+        assert!(error(&["--engine", "gpu", "--benchmark"]).starts_with("unknown engine"));
+        assert_eq!(error(&["--frobnicate"]), "unknown option --frobnicate");
+        assert!(error(&["glider", "acorn"]).starts_with("more than one pattern"));
+    }
+
+    #[test]
+    fn rejects_options_for_another_mode() {
+        assert!(error(&["--headless", "--benchmark"]).starts_with("only one of"));
+        assert!(error(&["--headless"]).starts_with("--headless needs"));
+        assert!(error(&["--generations", "10"]).starts_with("--generations needs"));
+        assert!(error(&["--output", "out.rle"]).contains("need --headless"));
+        assert!(error(&["--headless", "--until-stable", "--every", "2"]).starts_with("--every needs"));
+        assert!(error(&["--workload", "soup"]).starts_with("--workload needs"));
+        assert!(error(&["--verify", "--rule", "wireworld.rule"]).starts_with("--rule needs"));
+        assert!(parse(&["--headless", "--until-stable", "--stream", "diff", "--every", "2"]).is_ok());
+        assert!(parse(&["--headless", "--generations", "2147483647"]).is_ok());
+        assert!(error(&["--headless", "--generations", "2147483648"]).starts_with("--generations must be a number up to"));
+    }
+}
@@ -0,0 +1,263 @@
+use std::path::{
+    Component,
+    Path,
+};
+use crate::{
+    condition::*,
+    rule::*,
+};
+use crate::json::*;
+
+/// Every console command, by name, with its usage line for `help`.
+pub const COMMANDS: &[(&str, &str)] = &[
+    ("clear", "clear            empty the world"),
+    ("goto", "goto GEN         run up to generation GEN"),
+    ("help", "help             list the commands"),
+    ("load", "load FILE|ID     load a pattern file or library pattern"),
+    ("pause", "pause            stop the simulation"),
+    ("population", "population       show the generation and population"),
+    ("random", "random DENSITY [SEED]  fill the world with a random soup"),
+    ("rule", "rule B3/S23      change the rule"),
+    ("run", "run              start the simulation"),
+    ("save", "save FILE        write the world to FILE (.rle, .cells, .lif or .mc)"),
+    ("speed", "speed N          run N generations per second (up to 60000)"),
+    ("step", "step [N]         run N generations (default 1)"),
+    ("until", "until [COND]     pause once COND holds (pop < N, stable, gen N, change X Y W H or clear)"),
+    ("view", "view X Y         move the view to cell X,Y"),
+];
+
+/// The highest speed in generations per second, 1000 a frame.
+pub const MAX_SPEED: i32 = 60_000;
+
+/// A command from the console or the remote control.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Command {
+    /// Empty the world.
+    Clear,
+    /// Stop watching for conditions.
+    ClearConditions,
+    /// List the conditions being watched for.
+    Conditions,
+    /// Run up to the given generation.
+    Goto(i32),
+    /// List the commands.
+    Help,
+    /// Load a pattern file or library pattern by name, or, when the text is
+    /// given, the pattern file contents sent inline.
+    Load(String, Option<String>),
+    /// Stop the simulation.
+    Pause,
+    /// Report the generation and population.
+    Population,
+    /// Fill the world with a soup of the given density and seed, seeded from
+    /// the clock when there is no seed.
+    Random(f64, Option<u64>),
+    /// List the live cells in the region (position, size).
+    Region((usize, usize), (usize, usize)),
+    /// Change the rule.
+    Rule(Rule),
+    /// Start the simulation.
+    Run,
+    /// Write the world to a pattern file, in the format given by its extension.
+    Save(String),
+    /// Make the given cells live (`true`) or dead.
+    SetCells(Vec<(usize, usize)>, bool),
+    /// Run the given number of generations per second.
+    Speed(i32),
+    /// Run the given number of generations.
+    Step(i32),
+    /// Pause once the condition holds.
+    Until(Condition),
+    /// Move the view to the given cell.
+    View(i32, i32),
+}
+
+impl Command {
+    /// Parses a console line such as `step 10`.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap_or("");
+        let argument = words.collect::<Vec<_>>().join(" ");
+        let no_argument = |command| if argument.is_empty() {
+            Ok(command)
+        } else {
+            Err(format!("{} takes no argument", name))
+        };
+        let usage = || {
+            let usage = COMMANDS.iter().find(|(command, _)| *command == name).unwrap().1;
+            format!("usage: {}", usage.split("  ").next().unwrap())
+        };
+        match name {
+            "clear" => no_argument(Self::Clear),
+            "help" => no_argument(Self::Help),
+            "pause" => no_argument(Self::Pause),
+            "population" => no_argument(Self::Population),
+            "run" => no_argument(Self::Run),
+            "goto" => argument.parse().ok().filter(|&generation| generation >= 0).map(Self::Goto).ok_or_else(usage),
+            "load" if !argument.is_empty() => Ok(Self::Load(argument, None)),
+            "load" => Err(usage()),
+            "random" => {
+                let (density, seed) = argument.split_once(' ').map_or((argument.as_str(), None), |(density, seed)| (density, Some(seed.trim())));
+                let density = density.parse().ok().filter(|density| (0.0..=1.0).contains(density)).ok_or_else(usage)?;
+                let seed = seed.map(|seed| seed.parse::<u64>().map_err(|_| usage())).transpose()?;
+                Ok(Self::Random(density, seed))
+            }
+            "rule" => Rule::parse(&argument).map(Self::Rule).ok_or_else(usage),
+            "save" if !argument.is_empty() => Ok(Self::Save(argument)),
+            "save" => Err(usage()),
+            "speed" => argument.parse().ok().filter(|speed| (1..=MAX_SPEED).contains(speed)).map(Self::Speed).ok_or_else(usage),
+            "step" if argument.is_empty() => Ok(Self::Step(1)),
+            "step" => argument.parse().ok().filter(|&generations| generations > 0).map(Self::Step).ok_or_else(usage),
+            "until" if argument.is_empty() => Ok(Self::Conditions),
+            "until" if argument == "clear" => Ok(Self::ClearConditions),
+            "until" => Condition::parse(&argument).map(Self::Until),
+            "view" => match argument.split_once(' ').map(|(x, y)| (x.parse(), y.trim().parse())) {
+                Some((Ok(x), Ok(y))) => Ok(Self::View(x, y)),
+                _ => Err(usage()),
+            },
+            _ => Err(format!("unknown command \"{}\" (try help)", name)),
+        }
+    }
+
+    /// Parses a remote control request such as
+    /// `{"command": "step", "generations": 10}`.
+    pub fn from_json(request: &Json) -> Result<Self, String> {
+        let name = request.get("command").and_then(Json::as_str).ok_or("missing \"command\"")?;
+        let field = |field: &str| request.get(field).ok_or_else(|| format!("{} needs \"{}\"", name, field));
+        let int = |field_name: &str, min: i64| -> Result<i64, String> {
+            field(field_name)?.as_i64().filter(|&n| n >= min && n <= i32::MAX as i64)
+                .ok_or_else(|| format!("\"{}\" must be a whole number from {}", field_name, min))
+        };
+        match name {
+            "clear" => Ok(Self::Clear),
+            "help" => Ok(Self::Help),
+            "pause" => Ok(Self::Pause),
+            "population" => Ok(Self::Population),
+            "run" => Ok(Self::Run),
+            "goto" => Ok(Self::Goto(int("generation", 0)? as i32)),
+            "load" => match (request.get("pattern"), request.get("text")) {
+                (Some(Json::String(pattern)), None) => Ok(Self::Load(relative_path("pattern", pattern)?, None)),
+                (None, Some(Json::String(text))) => Ok(Self::Load("text".to_string(), Some(text.clone()))),
+                _ => Err("load needs either a \"pattern\" file or id, or the pattern \"text\"".to_string()),
+            },
+            "random" => {
+                let density = field("density")?.as_f64().filter(|density| (0.0..=1.0).contains(density))
+                    .ok_or("\"density\" must be between 0 and 1")?;
+                // seeds don't all fit in a JSON number, so they may also be strings as in the reply
+                let seed = match request.get("seed") {
+                    None => None,
+                    Some(Json::String(seed)) => Some(seed.parse::<u64>().map_err(|_| "\"seed\" must be a whole number")?),
+                    Some(seed) => Some(seed.as_i64().filter(|&seed| seed >= 0).ok_or("\"seed\" must be a whole number")? as u64),
+                };
+                Ok(Self::Random(density, seed))
+            }
+            "rule" => field("rule")?.as_str().and_then(Rule::parse).map(Self::Rule)
+                .ok_or_else(|| "\"rule\" must be a rule such as \"B3/S23\"".to_string()),
+            "save" => match field("file")? {
+                Json::String(file) => Ok(Self::Save(relative_path("file", file)?)),
+                _ => Err("\"file\" must be a string".to_string()),
+            },
+            "speed" => Some(int("speed", 1)? as i32).filter(|&speed| speed <= MAX_SPEED).map(Self::Speed)
+                .ok_or_else(|| format!("\"speed\" must be at most {}", MAX_SPEED)),
+            "step" if request.get("generations").is_none() => Ok(Self::Step(1)),
+            "step" => Ok(Self::Step(int("generations", 1)? as i32)),
+            "until" => match request.get("condition") {
+                None => Ok(Self::Conditions),
+                Some(Json::String(condition)) if condition == "clear" => Ok(Self::ClearConditions),
+                Some(Json::String(condition)) => Condition::parse(condition).map(Self::Until),
+                Some(_) => Err("\"condition\" must be a string".to_string()),
+            },
+            "view" => Ok(Self::View(int("x", i32::MIN as i64)? as i32, int("y", i32::MIN as i64)? as i32)),
+            "region" => Ok(Self::Region(
+                (int("x", 0)? as usize, int("y", 0)? as usize),
+                (int("width", 0)? as usize, int("height", 0)? as usize),
+            )),
+            "set_cells" => {
+                let alive = match request.get("alive") {
+                    None => true,
+                    Some(alive) => alive.as_bool().ok_or("\"alive\" must be true or false")?,
+                };
+                let cells = field("cells")?.as_array().ok_or("\"cells\" must be a list of [x, y] pairs")?;
+                let cells = cells.iter().map(|cell| match cell.as_array() {
+                    Some([x, y]) => x.as_i64().zip(y.as_i64())
+                        .filter(|&(x, y)| x >= 0 && y >= 0)
+                        .map(|(x, y)| (x as usize, y as usize)),
+                    _ => None,
+                }).collect::<Option<Vec<_>>>().ok_or("\"cells\" must be a list of [x, y] pairs")?;
+                Ok(Self::SetCells(cells, alive))
+            }
+            _ => Err(format!("unknown command \"{}\"", name)),
+        }
+    }
+}
+
+// remote clients may only name files under the working directory
+fn relative_path(field: &str, path: &str) -> Result<String, String> {
+    let mut components = Path::new(path).components().peekable();
+    if components.peek().is_some() && components.all(|component| matches!(component, Component::Normal(_) | Component::CurDir)) {
+        Ok(path.to_string())
+    } else {
+        Err(format!("\"{}\" must be a relative path without \"..\"", field))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(text: &str) -> Result<Command, String> {
+        Command::from_json(&Json::parse(text).unwrap())
+    }
+
+    #[test]
+    fn parses_console_lines() {
+        assert_eq!(Command::parse("step"), Ok(Command::Step(1)));
+        assert_eq!(Command::parse("  step   10 "), Ok(Command::Step(10)));
+        assert_eq!(Command::parse("load my pattern.rle"), Ok(Command::Load("my pattern.rle".to_string(), None)));
+        assert_eq!(Command::parse("random 0.3 42"), Ok(Command::Random(0.3, Some(42))));
+        assert_eq!(Command::parse("save world.mc"), Ok(Command::Save("world.mc".to_string())));
+        assert_eq!(Command::parse("view -5 7"), Ok(Command::View(-5, 7)));
+        assert_eq!(Command::parse("until clear"), Ok(Command::ClearConditions));
+        assert_eq!(Command::parse("step 0"), Err("usage: step [N]".to_string()));
+        assert_eq!(Command::parse("speed 60001"), Err("usage: speed N".to_string()));
+        assert_eq!(Command::parse("pause now"), Err("pause takes no argument".to_string()));
+        assert!(Command::parse("jump").unwrap_err().starts_with("unknown command"));
+    }
+
+    #[test]
+    fn parses_remote_requests() {
+        assert_eq!(request(r#"{"command": "step", "generations": 10}"#), Ok(Command::Step(10)));
+        assert_eq!(request(r#"{"command": "step"}"#), Ok(Command::Step(1)));
+        assert_eq!(request(r#"{"command": "load", "text": "x = 1, y = 1\no!"}"#),
+            Ok(Command::Load("text".to_string(), Some("x = 1, y = 1\no!".to_string()))));
+        assert_eq!(request(r#"{"command": "save", "file": "world.rle"}"#), Ok(Command::Save("world.rle".to_string())));
+        assert_eq!(request(r#"{"command": "set_cells", "cells": [[1, 2], [3, 4]], "alive": false}"#),
+            Ok(Command::SetCells(vec![(1, 2), (3, 4)], false)));
+        assert_eq!(request(r#"{"command": "random", "density": 0.5, "seed": "18446744073709551615"}"#),
+            Ok(Command::Random(0.5, Some(u64::MAX))));
+        assert_eq!(request(r#"{"command": "region", "x": 0, "y": 8, "width": 16, "height": 4}"#),
+            Ok(Command::Region((0, 8), (16, 4))));
+    }
+
+    #[test]
+    fn rejects_bad_requests() {
+        assert_eq!(request(r#"{"generations": 10}"#), Err("missing \"command\"".to_string()));
+        assert_eq!(request(r#"{"command": "fly"}"#), Err("unknown command \"fly\"".to_string()));
+        assert_eq!(request(r#"{"command": "rule"}"#), Err("rule needs \"rule\"".to_string()));
+        assert!(request(r#"{"command": "step", "generations": 0}"#).is_err());
+        assert!(request(r#"{"command": "step", "generations": 1.5}"#).is_err());
+        assert!(request(r#"{"command": "speed", "speed": 60001}"#).is_err());
+        assert!(request(r#"{"command": "random", "density": 2}"#).is_err());
+        assert!(request(r#"{"command": "random", "density": 0.5, "seed": -1}"#).is_err());
+        assert!(request(r#"{"command": "set_cells", "cells": [[1, -2]]}"#).is_err());
+        assert!(request(r#"{"command": "set_cells", "cells": [[1, 2, 3]]}"#).is_err());
+        assert!(request(r#"{"command": "load", "pattern": "glider", "text": "o!"}"#).is_err());
+        assert!(request(r#"{"command": "save", "file": 3}"#).is_err());
+        assert!(request(r#"{"command": "save", "file": "/home/user/.bashrc"}"#).is_err());
+        assert!(request(r#"{"command": "save", "file": "patterns/../../out.rle"}"#).is_err());
+        assert!(request(r#"{"command": "save", "file": ""}"#).is_err());
+        assert!(request(r#"{"command": "load", "pattern": "/etc/passwd"}"#).is_err());
+        assert_eq!(request(r#"{"command": "load", "pattern": "./patterns/gun.rle"}"#),
+            Ok(Command::Load("./patterns/gun.rle".to_string(), None)));
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::VecDeque;
+use std::fmt;
+use crate::{
+    analysis::MAX_PERIOD,
+    engine::*,
+    life_cell::*,
+};
+
+/// Something to stop a run for.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Condition {
+    /// The population dropped below the given count.
+    PopulationBelow(usize),
+    /// The world died out or came back to a state from the last
+    /// [`MAX_PERIOD`] generations.
+    Stable,
+    /// The given generation was reached.
+    Generation(i32),
+    /// A cell in the region (position, size) was born or died.
+    RegionChanged((usize, usize), (usize, usize)),
+}
+
+impl Condition {
+    /// Parses `population < N` (or `pop < N`), `stable`, `generation N`
+    /// (or `gen N`) and `change X Y WIDTH HEIGHT`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.replace('<', " < ");
+        let words: Vec<_> = text.split_whitespace().collect();
+        let number = |word: &str| word.parse::<usize>().map_err(|_| format!("expected a number, not \"{}\"", word));
+        match words.as_slice() {
+            ["population" | "pop", "<", count] => Ok(Self::PopulationBelow(number(count)?)),
+            ["stable"] => Ok(Self::Stable),
+            ["generation" | "gen", generation] => generation.parse().ok().filter(|&generation| generation >= 0)
+                .map(Self::Generation)
+                .ok_or_else(|| format!("expected a generation, not \"{}\"", generation)),
+            ["change", x, y, width, height] => Ok(Self::RegionChanged((number(x)?, number(y)?), (number(width)?, number(height)?))),
+            _ => Err(format!(
+                "unknown condition \"{}\" (population < N, stable, generation N or change X Y WIDTH HEIGHT)",
+                words.join(" "),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::PopulationBelow(count) => write!(f, "population < {}", count),
+            Self::Stable => write!(f, "stable"),
+            Self::Generation(generation) => write!(f, "generation {}", generation),
+            Self::RegionChanged((x, y), (width, height)) => write!(f, "change {} {} {} {}", x, y, width, height),
+        }
+    }
+}
+
+/// A list of conditions, checked after every generation of a run.
+#[derive(Default, Debug, Clone)]
+pub struct Watch {
+    conditions: Vec<Condition>,
+    history: VecDeque<u64>,
+    last_generation: Option<i32>,
+}
+
+impl Watch {
+    /// The conditions being watched for, in the order they were added.
+    pub fn conditions(&self) -> &[Condition] {
+        &self.conditions
+    }
+
+    /// Starts watching for `condition`.
+    pub fn add(&mut self, condition: Condition) {
+        if !self.conditions.contains(&condition) {
+            self.conditions.push(condition);
+        }
+    }
+
+    /// Stops watching for anything.
+    pub fn clear(&mut self) {
+        self.conditions.clear();
+        self.history.clear();
+        self.last_generation = None;
+    }
+
+    /// Checks `world`, just advanced to `generation` with `population` live
+    /// cells. The first condition that holds is removed and returned.
+    ///
+    /// The states seen for [`Condition::Stable`] are forgotten whenever the
+    /// generation doesn't follow the one last checked, e.g. after a new
+    /// pattern is loaded.
+    pub fn check(&mut self, world: &World, generation: i32, population: usize) -> Option<Condition> {
+        if self.conditions.is_empty() {
+            return None;
+        }
+        if self.last_generation.and_then(|last| last.checked_add(1)) != Some(generation) {
+            self.history.clear();
+        }
+        self.last_generation = Some(generation);
+        let mut repeated = false;
+        if self.conditions.contains(&Condition::Stable) {
+            let hash = world.state_hash();
+            repeated = population == 0 || self.history.contains(&hash);
+            if self.history.len() == MAX_PERIOD {
+                self.history.pop_front();
+            }
+            self.history.push_back(hash);
+        }
+        let idx = self.conditions.iter().position(|condition| match *condition {
+            Condition::PopulationBelow(count) => population < count,
+            Condition::Stable => repeated,
+            Condition::Generation(target) => generation >= target,
+            Condition::RegionChanged((x, y), (width, height)) => world.changes()
+                .any(|(cx, cy)| (x..x.saturating_add(width)).contains(&cx) && (y..y.saturating_add(height)).contains(&cy)),
+        })?;
+        Some(self.conditions.remove(idx))
+    }
+}
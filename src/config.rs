@@ -0,0 +1,219 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+use piston_window::Key;
+use crate::{
+    cli::*,
+    input_role::*,
+};
+
+pub(crate) const DEFAULT_KEY_BINDINGS: &[(Key, InputRole)] = &[
+    (Key::D1,       InputRole::Progress1),
+    (Key::D2,       InputRole::Progress2),
+    (Key::D3,       InputRole::Progress4),
+    (Key::D4,       InputRole::Progress8),
+    (Key::RightBracket, InputRole::SpanUp),
+    (Key::LeftBracket, InputRole::SpanDown),
+    (Key::P,        InputRole::Pause),
+    (Key::O,        InputRole::OneTick),
+    (Key::H,        InputRole::Home),
+    (Key::X,        InputRole::DiffMode),
+    (Key::T,        InputRole::TrailMode),
+    (Key::Equals,   InputRole::Grow),
+    (Key::Minus,    InputRole::Shrink),
+    (Key::C,        InputRole::Crop),
+    (Key::R,        InputRole::RandomWorld),
+    (Key::F,        InputRole::RandomView),
+    (Key::M,        InputRole::Symmetry),
+    (Key::E,        InputRole::Export),
+    (Key::F5,       InputRole::SaveSession),
+    (Key::F9,       InputRole::LoadSession),
+    (Key::Escape,   InputRole::Quit),
+    (Key::Y,        InputRole::Yes),
+    (Key::N,        InputRole::No),
+    (Key::L,        InputRole::Library),
+    (Key::Backquote, InputRole::Console),
+    (Key::Z,        InputRole::Button0),
+    (Key::Space,    InputRole::Button0),
+    (Key::W,        InputRole::Up),
+    (Key::D,        InputRole::Right),
+    (Key::S,        InputRole::Down),
+    (Key::A,        InputRole::Left),
+    (Key::Up,       InputRole::Up),
+    (Key::Right,    InputRole::Right),
+    (Key::Down,     InputRole::Down),
+    (Key::Left,     InputRole::Left),
+];
+
+// keys that may appear in the [keys] section, named as piston names them
+const BINDABLE_KEYS: &[Key] = &[
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M,
+    Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::D0, Key::D1, Key::D2, Key::D3, Key::D4, Key::D5, Key::D6, Key::D7, Key::D8, Key::D9,
+    Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12,
+    Key::Up, Key::Right, Key::Down, Key::Left,
+    Key::Home, Key::End, Key::PageUp, Key::PageDown, Key::Insert, Key::Delete,
+    Key::Space, Key::Return, Key::Escape, Key::Tab, Key::Backspace,
+    Key::Minus, Key::Equals, Key::LeftBracket, Key::RightBracket, Key::Backslash,
+    Key::Semicolon, Key::Quote, Key::Backquote, Key::Comma, Key::Period, Key::Slash,
+    Key::NumPad0, Key::NumPad1, Key::NumPad2, Key::NumPad3, Key::NumPad4,
+    Key::NumPad5, Key::NumPad6, Key::NumPad7, Key::NumPad8, Key::NumPad9,
+    Key::NumPadPlus, Key::NumPadMinus, Key::NumPadMultiply, Key::NumPadDivide, Key::NumPadEnter, Key::NumPadPeriod,
+    Key::LShift, Key::RShift, Key::LCtrl, Key::RCtrl, Key::LAlt, Key::RAlt,
+];
+
+fn key_from_name(name: &str) -> Option<Key> {
+    BINDABLE_KEYS.iter().copied().find(|key| format!("{:?}", key).eq_ignore_ascii_case(name))
+}
+
+/// Where config.ini lives: `life-rs/config.ini` under `$XDG_CONFIG_HOME`,
+/// `~/.config` or `%APPDATA%`.
+pub fn config_path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(config_dir.join(env!("CARGO_PKG_NAME")).join("config.ini"))
+}
+
+/// A bad line in config.ini.
+#[derive(Debug)]
+pub struct ConfigError {
+    /// The line number, from 1.
+    pub line: usize,
+    /// What is wrong with it.
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// The settings read from config.ini: display defaults and key bindings.
+#[derive(Default, Debug, Clone)]
+pub struct Config {
+    display: Vec<(String, String)>,
+    // roles named in [keys] lose their default keys
+    key_overrides: BTreeMap<InputRole, Vec<Key>>,
+}
+
+impl Config {
+    /// Parses the text of a config file. Bad lines are reported and skipped
+    /// so one typo doesn't lose the rest of the file.
+    pub fn parse(text: &str) -> (Self, Vec<ConfigError>) {
+        let mut config = Self::default();
+        let mut errors = Vec::new();
+        let mut section = None;
+        let mut scratch = Args::default();
+        for (line_no, line) in text.lines().enumerate().map(|(i, line)| (i + 1, line.trim())) {
+            let mut error = |message: String| errors.push(ConfigError { line: line_no, message });
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                let name = name.trim();
+                if name != "display" && name != "keys" {
+                    error(format!("unknown section [{}]", name));
+                }
+                section = Some(name.to_string());
+                continue;
+            }
+            let Some((name, value)) = line.split_once('=').map(|(name, value)| (name.trim(), value.trim())) else {
+                error(format!("expected \"name = value\", not \"{}\"", line));
+                continue;
+            };
+            match section.as_deref() {
+                Some("display") => match scratch.set_display_option(name, value) {
+                    Ok(()) => config.display.push((name.to_string(), value.to_string())),
+                    Err(e) => error(e),
+                },
+                Some("keys") => {
+                    let Some(role) = InputRole::from_name(name) else {
+                        error(format!("unknown role \"{}\"", name));
+                        continue;
+                    };
+                    let keys = config.key_overrides.entry(role).or_default();
+                    for key_name in value.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+                        match key_from_name(key_name) {
+                            Some(key) => keys.push(key),
+                            None => error(format!("unknown key \"{}\"", key_name)),
+                        }
+                    }
+                }
+                // an unknown section, already reported
+                Some(_) => (),
+                None => error(format!("\"{}\" is outside any section", name)),
+            }
+        }
+        (config, errors)
+    }
+
+    /// Reads and parses a config file.
+    pub fn load<P: AsRef<Path>>(path: P) -> std::io::Result<(Self, Vec<ConfigError>)> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?))
+    }
+
+    /// Sets the display options from the [display] section in `args`.
+    pub fn apply_display(&self, args: &mut Args) {
+        for (name, value) in &self.display {
+            // validated by parse
+            let _ = args.set_display_option(name, value);
+        }
+    }
+
+    pub(crate) fn key_bindings(&self) -> Vec<(Key, InputRole)> {
+        let mut key_bindings: Vec<_> = DEFAULT_KEY_BINDINGS.iter()
+            .filter(|(_, role)| !self.key_overrides.contains_key(role))
+            .copied()
+            .collect();
+        for (role, keys) in &self.key_overrides {
+            key_bindings.extend(keys.iter().map(|key| (*key, *role)));
+        }
+        key_bindings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_display_options_and_keys() {
+        let (config, errors) = Config::parse("# defaults\n[display]\nworld = 1024x768\nscale = 2\n\n\
+            ; keys\n[keys]\nPause = P, return\nQuit = Escape\nQuit = Q\n");
+        assert!(errors.is_empty(), "{:?}", errors);
+        let mut args = Args::default();
+        config.apply_display(&mut args);
+        assert_eq!(args.world_size, (1024, 768));
+        assert_eq!(args.pixel_scale, 2);
+
+        let key_bindings = config.key_bindings();
+        let keys = |role| key_bindings.iter().filter(|(_, r)| *r == role).map(|(key, _)| *key).collect::<Vec<_>>();
+        assert_eq!(keys(InputRole::Pause), [Key::P, Key::Return]);
+        assert_eq!(keys(InputRole::Quit), [Key::Escape, Key::Q]);
+        assert_eq!(keys(InputRole::Export), [Key::E]);
+    }
+
+    #[test]
+    fn reports_bad_lines_and_keeps_the_rest() {
+        let (config, errors) = Config::parse("scale = 2\n[display]\nscale = 0\nmargin\nframes = 60\nmargin = 4\n\
+            [sounds]\nvolume = 11\n[keys]\nJump = Space\nPause = P, Hyper\n");
+        let lines: Vec<_> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, [1, 3, 4, 5, 7, 10, 11]);
+        let mut args = Args::default();
+        config.apply_display(&mut args);
+        assert_eq!(args.pixel_scale, Args::default().pixel_scale);
+        assert_eq!(args.window_margin, 4);
+        assert!(config.key_bindings().contains(&(Key::P, InputRole::Pause)));
+    }
+}
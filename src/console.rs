@@ -0,0 +1,183 @@
+use piston_window::Key;
+use bgsp_lib2::{
+    bgsp_common::*,
+    bg_plane::*,
+};
+use crate::pattern_library::*;
+use crate::command::*;
+
+const CONSOLE_LINES: usize = 10;
+const MAX_HISTORY: usize = 100;
+
+// what the viewer typed since the last frame, collected while the console is open
+#[derive(Default, Debug)]
+pub struct TextInput {
+    pub text: String,
+    pub keys: Vec<Key>,
+}
+
+#[derive(Default, Debug)]
+pub struct Console {
+    open: bool,
+    line: String,
+    history: Vec<String>,
+    // position while browsing the history, and the line being typed before that
+    history_pos: Option<usize>,
+    draft: String,
+    output: Vec<(String, u32)>,
+}
+
+impl Console {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    pub fn clear(&self, bg0: &mut BgPlane, vm_rect_size: (i32, i32)) {
+        let (rows, cols) = Self::area(vm_rect_size);
+        for row in rows {
+            bg0.set_cur_pos(0, row).put_code_n(' ', cols);
+        }
+    }
+
+    pub fn print<S: Into<String>>(&mut self, text: S, palette: u32) {
+        self.output.push((text.into(), palette));
+        let excess = self.output.len().saturating_sub(CONSOLE_LINES - 1);
+        self.output.drain(..excess);
+    }
+
+    // returns the lines entered since the last call; Escape closes the console
+    pub fn feed(&mut self, input: &mut TextInput) -> Vec<String> {
+        let mut entered = Vec::new();
+        self.line.extend(input.text.drain(..).filter(|&c| !c.is_control() && c != '`'));
+        for key in input.keys.drain(..) {
+            match key {
+                Key::Return | Key::NumPadEnter => {
+                    let line = std::mem::take(&mut self.line).trim().to_string();
+                    self.history_pos = None;
+                    if !line.is_empty() {
+                        self.print(format!("> {}", line), 5);
+                        if self.history.last() != Some(&line) {
+                            self.history.push(line.clone());
+                        }
+                        if self.history.len() > MAX_HISTORY {
+                            self.history.remove(0);
+                        }
+                        entered.push(line);
+                    }
+                }
+                Key::Backspace => {
+                    self.line.pop();
+                }
+                Key::Tab => self.complete(),
+                Key::Up if !self.history.is_empty() => {
+                    let pos = match self.history_pos {
+                        None => {
+                            self.draft = self.line.clone();
+                            self.history.len() - 1
+                        }
+                        Some(pos) => pos.saturating_sub(1),
+                    };
+                    self.history_pos = Some(pos);
+                    self.line = self.history[pos].clone();
+                }
+                Key::Down => if let Some(pos) = self.history_pos {
+                    if pos + 1 < self.history.len() {
+                        self.history_pos = Some(pos + 1);
+                        self.line = self.history[pos + 1].clone();
+                    } else {
+                        self.history_pos = None;
+                        self.line = std::mem::take(&mut self.draft);
+                    }
+                },
+                Key::Escape | Key::Backquote => self.open = false,
+                _ => {}
+            }
+        }
+        entered
+    }
+
+    fn complete(&mut self) {
+        let (head, word) = match self.line.rfind(' ') {
+            Some(idx) => self.line.split_at(idx + 1),
+            None => ("", self.line.as_str()),
+        };
+        let candidates: Vec<String> = if head.is_empty() {
+            COMMANDS.iter().map(|(name, _)| name.to_string()).filter(|name| name.starts_with(word)).collect()
+        } else if head.trim() == "load" {
+            load_candidates(word)
+        } else {
+            Vec::new()
+        };
+        let Some(first) = candidates.first() else {
+            return;
+        };
+        // in bytes, but always at a character boundary
+        let common_len = candidates.iter().fold(first.len(), |len, candidate| {
+            first[..len].chars().zip(candidate.chars()).take_while(|(a, b)| a == b).map(|(c, _)| c.len_utf8()).sum()
+        });
+        let mut line = format!("{}{}", head, &first[..common_len]);
+        if candidates.len() == 1 && !first.ends_with('/') {
+            line.push(' ');
+        } else if common_len == word.len() {
+            self.print(candidates.join(" "), 3);
+        }
+        self.line = line;
+    }
+
+    fn area(vm_rect_size: (i32, i32)) -> (std::ops::Range<i32>, i32) {
+        let rows = vm_rect_size.1 / PATTERN_SIZE as i32;
+        let cols = vm_rect_size.0 / PATTERN_SIZE as i32;
+        ((rows - CONSOLE_LINES as i32).max(0)..rows, cols)
+    }
+
+    pub fn draw(&self, bg0: &mut BgPlane, vm_rect_size: (i32, i32)) {
+        if !self.open {
+            return;
+        }
+        let (rows, cols) = Self::area(vm_rect_size);
+        let blank_lines = CONSOLE_LINES - 1 - self.output.len();
+        for (row, idx) in rows.zip(0..) {
+            let (text, palette) = if idx < blank_lines {
+                (String::new(), 1)
+            } else if let Some((text, palette)) = self.output.get(idx - blank_lines) {
+                (text.clone(), *palette)
+            } else {
+                // keep the end of a long line in view while typing
+                let prompt = format!("> {}_", self.line);
+                let skip = prompt.chars().count().saturating_sub(cols as usize);
+                (prompt.chars().skip(skip).collect(), 4)
+            };
+            let text: String = text.chars().take(cols as usize).collect();
+            bg0.set_cur_pos(0, row)
+                .put_string(&text, Some(&CharAttributes::new(palette, BgSymmetry::Normal)))
+                .put_code_n(' ', cols - text.chars().count() as i32)
+            ;
+        }
+    }
+}
+
+fn load_candidates(word: &str) -> Vec<String> {
+    let (dir, prefix) = match word.rfind('/') {
+        Some(idx) => word.split_at(idx + 1),
+        None => ("", word),
+    };
+    let mut candidates = Vec::new();
+    if dir.is_empty() {
+        candidates.extend(LIBRARY.iter().map(|entry| entry.id.to_string()).filter(|id| id.starts_with(prefix)));
+    }
+    if let Ok(entries) = std::fs::read_dir(if dir.is_empty() { "." } else { dir }) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with(prefix) && !name.starts_with('.') {
+                let slash = if entry.path().is_dir() { "/" } else { "" };
+                candidates.push(format!("{}{}{}", dir, name, slash));
+            }
+        }
+    }
+    candidates.sort();
+    candidates
+}
@@ -0,0 +1,126 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{
+        Hash,
+        Hasher,
+    },
+};
+use crate::{
+    life_cell::*,
+    multi_state::*,
+    rule::*,
+};
+
+/// A simulation backend that can run a two-state rule, so backends can be
+/// compared on the same workload.
+pub trait Engine {
+    /// Short name identifying the backend.
+    fn name(&self) -> &'static str;
+    /// The grid size in cells (width, height).
+    fn size(&self) -> (usize, usize);
+    /// Whether the cell at `pos` is alive.
+    fn is_alive(&self, pos: (usize, usize)) -> bool;
+    /// Advances by one generation.
+    fn step(&mut self);
+    /// Number of live cells.
+    fn population(&self) -> usize;
+
+    /// A hash of which cells are alive, equal for engines in the same state.
+    fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        let size = self.size();
+        for y in 0..size.1 {
+            for x in 0..size.0 {
+                if self.is_alive((x, y)) {
+                    (x, y).hash(&mut hasher);
+                }
+            }
+        }
+        hasher.finish()
+    }
+}
+
+impl Engine for World {
+    fn name(&self) -> &'static str {
+        "world"
+    }
+
+    fn size(&self) -> (usize, usize) {
+        World::size(self)
+    }
+
+    fn is_alive(&self, pos: (usize, usize)) -> bool {
+        self.read_cell(pos).state == CellState::Live
+    }
+
+    fn step(&mut self) {
+        self.update_world();
+    }
+
+    fn population(&self) -> usize {
+        self.live_cells().count()
+    }
+
+    fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for pos in self.live_cells() {
+            pos.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+/// A [`StateGrid`] running a two-state [`Rule`], i.e. the engine used for rule
+/// files, applied to Life. Its edges wrap the way a [`World`]'s do.
+pub struct StateGridEngine {
+    grid: StateGrid,
+    rule: Rule,
+}
+
+impl StateGridEngine {
+    /// Copies the cells and rule of `world`.
+    pub fn from_world(world: &World) -> Self {
+        let mut grid = StateGrid::new(world.size());
+        grid.set_wrap(Wrap::Ring);
+        for pos in world.live_cells() {
+            grid.set_cell(pos, 1);
+        }
+        Self { grid, rule: world.rule() }
+    }
+}
+
+impl Engine for StateGridEngine {
+    fn name(&self) -> &'static str {
+        "state-grid"
+    }
+
+    fn size(&self) -> (usize, usize) {
+        self.grid.size()
+    }
+
+    fn is_alive(&self, pos: (usize, usize)) -> bool {
+        self.grid.read_cell(pos) != 0
+    }
+
+    fn step(&mut self) {
+        self.grid.update(&self.rule);
+    }
+
+    fn population(&self) -> usize {
+        self.grid.population()
+    }
+}
+
+/// A function building an engine from the cells and rule of a world.
+pub type EngineBuilder = fn(&World) -> Box<dyn Engine>;
+
+/// Every available engine, by name.
+pub const ENGINES: &[(&str, EngineBuilder)] = &[
+    ("world", |world| Box::new(world.resized(world.size(), (0, 0)))),
+    ("state-grid", |world| Box::new(StateGridEngine::from_world(world))),
+];
+
+/// Looks up an engine builder by name.
+pub fn find_engine(name: &str) -> Option<EngineBuilder> {
+    ENGINES.iter().find(|(engine_name, _)| *engine_name == name).map(|&(_, builder)| builder)
+}
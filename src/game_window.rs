@@ -47,7 +47,7 @@ impl GameWindow {
             let window_setting = piston_window::WindowSettings::new(&window_title, window_rect_size)
                 .samples(0)
                 .fullscreen(full_screen)
-                .exit_on_esc(true)
+                .exit_on_esc(false)
                 .graphics_api(OPENGL_VER)
                 .vsync(true)
                 .resizable(false)
@@ -0,0 +1,99 @@
+use std::io::{
+    self,
+    BufWriter,
+};
+use life_rs::{
+    analysis::*,
+    benchmark::{self, *},
+    engine::*,
+    soup::*,
+    verify::{self, *},
+    life_cell::*,
+    pattern::*,
+    stream::*,
+    cli::*,
+};
+
+const VERIFY_SOUPS: u32 = 4;
+const VERIFY_GENERATIONS: u32 = 1000;
+// how many differing cells to list for a divergence
+const MAX_REPORTED_CELLS: usize = 16;
+
+pub fn run(args: &Args, mut world: World, generation: i32) -> Result<Summary, PatternError> {
+    let summary = match args.stream {
+        Some(format) => {
+            let mut recorder = Recorder::new(BufWriter::new(io::stdout().lock()), format, args.every.unwrap_or(1));
+            let mut result = recorder.start(&world, generation);
+            let summary = simulate_with(&mut world, generation, args.generations, args.until_stable, |world, generation| {
+                result = recorder.record(world, generation);
+                result.is_ok()
+            });
+            result = result.and_then(|_| recorder.finish(&world, summary.generation));
+            match result {
+                // the reader went away, e.g. "| head"
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
+                result => result?,
+            }
+            summary
+        }
+        None => simulate(&mut world, generation, args.generations, args.until_stable),
+    };
+    let mut pattern = Pattern::from_world(&world, None);
+    pattern.comments.push(format!("generation {}", summary.generation));
+    match &args.output {
+        Some(path) => {
+            save(&pattern, path)?;
+            if args.stream.is_some() {
+                eprint!("{}", summary.to_text());
+            } else {
+                print!("{}", summary.to_text());
+            }
+        }
+        None if args.stream.is_some() => eprint!("{}", summary.to_text()),
+        None => {
+            print!("{}", write(&pattern, Format::Rle));
+            eprint!("{}", summary.to_text());
+        }
+    }
+    Ok(summary)
+}
+
+pub fn benchmark(args: &Args) {
+    let selected = |names: &[String], name: &str| names.is_empty() || names.iter().any(|n| n == name);
+    for workload in WORKLOADS.iter().filter(|workload| selected(&args.workloads, workload.name)) {
+        let generations = workload.generations();
+        for &(_, builder) in ENGINES.iter().filter(|(name, _)| selected(&args.engines, name)) {
+            println!("{}", benchmark::run(workload, builder, generations).to_json());
+        }
+    }
+}
+
+// returns whether every engine agreed with the reference
+pub fn verify(args: &Args) -> bool {
+    let generations = args.generations.unwrap_or(VERIFY_GENERATIONS);
+    let mut agreed = true;
+    for seed in 1..=args.soups.unwrap_or(VERIFY_SOUPS) as u64 {
+        let mut world = World::new(args.world_size);
+        Soup::new(seed, RANDOM_DENSITY, Symmetry::C1).fill(&mut world, ((0, 0), args.world_size));
+        let engines = ENGINES.iter()
+            .filter(|(name, _)| *name != "world")
+            .filter(|(name, _)| args.engines.is_empty() || args.engines.iter().any(|n| n == name));
+        for &(name, builder) in engines {
+            match verify::verify(&world, builder, generations) {
+                None => println!("seed {}, {}: agrees for {} generations", seed, name, generations),
+                Some(Divergence { generation, cells }) => {
+                    agreed = false;
+                    println!("seed {}, {}: diverges at generation {} in {} cells", seed, name, generation, cells.len());
+                    for &((x, y), alive) in cells.iter().take(MAX_REPORTED_CELLS) {
+                        println!("    {},{}: {} in world, {} in {}", x, y,
+                            if alive { "live" } else { "dead" }, if alive { "dead" } else { "live" }, name);
+                    }
+                    if cells.len() > MAX_REPORTED_CELLS {
+                        println!("    ...");
+                    }
+                }
+            }
+        }
+    }
+    agreed
+}
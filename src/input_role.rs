@@ -7,7 +7,25 @@ pub enum InputRole {
     Progress2,
     Progress4,
     Progress8,
+    SpanUp,
+    SpanDown,
     Home,
+    DiffMode,
+    TrailMode,
+    Grow,
+    Shrink,
+    Crop,
+    RandomWorld,
+    RandomView,
+    Symmetry,
+    Export,
+    SaveSession,
+    LoadSession,
+    Quit,
+    Library,
+    Console,
+    Yes,
+    No,
     Up,
     Right,
     Down,
@@ -18,6 +36,22 @@ pub enum InputRole {
 impl InputRole {
     #[allow(non_upper_case_globals)]
     pub const Whole: Self = Self::_EndOfVariants;
+
+    pub const ALL: [Self; Self::_EndOfVariants as usize] = [
+        Self::Button0, Self::Pause, Self::OneTick,
+        Self::Progress1, Self::Progress2, Self::Progress4, Self::Progress8,
+        Self::SpanUp, Self::SpanDown,
+        Self::Home, Self::DiffMode, Self::TrailMode,
+        Self::Grow, Self::Shrink, Self::Crop,
+        Self::RandomWorld, Self::RandomView, Self::Symmetry,
+        Self::Export, Self::SaveSession, Self::LoadSession,
+        Self::Quit, Self::Library, Self::Console, Self::Yes, Self::No,
+        Self::Up, Self::Right, Self::Down, Self::Left,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|role| format!("{:?}", role).eq_ignore_ascii_case(name))
+    }
 }
 
 #[derive(Default, Debug, Clone, Copy)]
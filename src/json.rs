@@ -0,0 +1,421 @@
+use std::fmt;
+
+// arrays and objects nested deeper than this are refused rather than
+// recursed into, so a hostile line can't overflow the stack
+const MAX_DEPTH: usize = 64;
+
+/// A JSON value. Displaying it writes it as compact JSON.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Json {
+    /// `null`.
+    Null,
+    /// `true` or `false`.
+    Bool(bool),
+    /// A number.
+    Number(f64),
+    /// A string.
+    String(String),
+    /// An array.
+    Array(Vec<Json>),
+    /// An object, with its members in the order they were written.
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parses a single JSON value, with nothing but whitespace around it.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parser = Parser { chars: text.chars().collect(), pos: 0, depth: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos < parser.chars.len() {
+            return Err(format!("unexpected '{}' at {}", parser.chars[parser.pos], parser.pos));
+        }
+        Ok(value)
+    }
+
+    /// The member `key` of an object.
+    pub fn get(&self, key: &str) -> Option<&Self> {
+        match self {
+            Self::Object(members) => members.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// The value of a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// The value of a number that is whole and small enough to be exact.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_f64().filter(|n| n.fract() == 0.0 && n.abs() < 9.0e15).map(|n| n as i64)
+    }
+
+    /// The value of `true` or `false`.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// The text of a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The items of an array.
+    pub fn as_array(&self) -> Option<&[Self]> {
+        match self {
+            Self::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Self {
+        Self::Bool(b)
+    }
+}
+
+impl From<i32> for Json {
+    fn from(n: i32) -> Self {
+        Self::Number(n as f64)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Self {
+        Self::Number(n as f64)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Self {
+        Self::String(s.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Self {
+        Self::String(s)
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Null => write!(f, "null"),
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::Number(n) if n.is_finite() => write!(f, "{}", n),
+            Self::Number(_) => write!(f, "null"),
+            Self::String(s) => write_string(f, s),
+            Self::Array(items) => {
+                write!(f, "[")?;
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Self::Object(members) => {
+                write!(f, "{{")?;
+                for (idx, (name, value)) in members.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, name)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("'{}'", c)))
+        }
+    }
+
+    fn unexpected(&self, wanted: &str) -> String {
+        match self.chars.get(self.pos) {
+            Some(c) => format!("expected {} but found '{}' at {}", wanted, c, self.pos),
+            None => format!("expected {} but the text ended", wanted),
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        let end = self.pos + word.len();
+        if end <= self.chars.len() && self.chars[self.pos..end].iter().copied().eq(word.chars()) {
+            self.pos = end;
+            Ok(value)
+        } else {
+            Err(self.unexpected("a value"))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        match self.peek() {
+            Some('{' | '[') if self.depth == MAX_DEPTH => Err(format!("nested more than {} deep at {}", MAX_DEPTH, self.pos)),
+            Some('{') => self.nested(Self::object),
+            Some('[') => self.nested(Self::array),
+            Some('"') => self.string().map(Json::String),
+            Some('t') => self.literal("true", Json::Bool(true)),
+            Some('f') => self.literal("false", Json::Bool(false)),
+            Some('n') => self.literal("null", Json::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            _ => Err(self.unexpected("a value")),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Json, String>) -> Result<Json, String> {
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            if self.peek() != Some('"') {
+                return Err(self.unexpected("a member name"));
+            }
+            let name = self.string()?;
+            self.expect(':')?;
+            members.push((name, self.value()?));
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(Json::Object(members));
+                }
+                _ => return Err(self.unexpected("',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(Json::Array(items));
+                }
+                _ => return Err(self.unexpected("',' or ']'")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            let Some(&c) = self.chars.get(self.pos) else {
+                return Err(self.unexpected("'\"'"));
+            };
+            self.pos += 1;
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let Some(&escape) = self.chars.get(self.pos) else {
+                        return Err(self.unexpected("an escape"));
+                    };
+                    self.pos += 1;
+                    match escape {
+                        '"' | '\\' | '/' => s.push(escape),
+                        'b' => s.push('\u{8}'),
+                        'f' => s.push('\u{c}'),
+                        'n' => s.push('\n'),
+                        'r' => s.push('\r'),
+                        't' => s.push('\t'),
+                        'u' => {
+                            let mut code = self.hex4()?;
+                            // a surrogate pair spells one character outside the BMP
+                            if (0xd800..0xdc00).contains(&code) && self.chars.get(self.pos..self.pos + 2) == Some(&['\\', 'u']) {
+                                let pos = self.pos;
+                                self.pos += 2;
+                                match self.hex4() {
+                                    Ok(low) if (0xdc00..0xe000).contains(&low) => code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00),
+                                    // not a low surrogate, so the next escape stands on its own
+                                    _ => self.pos = pos,
+                                }
+                            }
+                            s.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                        }
+                        _ => {
+                            self.pos -= 1;
+                            return Err(self.unexpected("an escape"));
+                        }
+                    }
+                }
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits: String = self.chars.iter().skip(self.pos).take(4).collect();
+        let code = u32::from_str_radix(&digits, 16).ok().filter(|_| digits.len() == 4)
+            .ok_or_else(|| self.unexpected("four hex digits"))?;
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.pos;
+        while self.chars.get(self.pos).is_some_and(|&c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')) {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        text.parse().map(Json::Number).map_err(|_| format!("invalid number \"{}\" at {}", text, start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(text: &str) -> String {
+        match Json::parse(text) {
+            Ok(Json::String(s)) => s,
+            other => panic!("expected a string from {}, got {:?}", text, other),
+        }
+    }
+
+    #[test]
+    fn parses_values() {
+        let value = Json::parse(" {\"command\": \"set_cells\", \"cells\": [[1, 2], [3, -4.5e1]], \"alive\": true, \"id\": null} ").unwrap();
+        assert_eq!(value.get("command").and_then(Json::as_str), Some("set_cells"));
+        assert_eq!(value.get("alive").and_then(Json::as_bool), Some(true));
+        assert_eq!(value.get("id"), Some(&Json::Null));
+        assert_eq!(value.get("missing"), None);
+        let cells = value.get("cells").and_then(Json::as_array).unwrap();
+        assert_eq!(cells[1], Json::Array(vec![Json::Number(3.0), Json::Number(-45.0)]));
+        assert_eq!(Json::parse("[]"), Ok(Json::Array(Vec::new())));
+        assert_eq!(Json::parse("{}"), Ok(Json::Object(Vec::new())));
+    }
+
+    #[test]
+    fn reads_only_exact_whole_numbers_as_integers() {
+        assert_eq!(Json::parse("42").unwrap().as_i64(), Some(42));
+        assert_eq!(Json::parse("-7").unwrap().as_i64(), Some(-7));
+        assert_eq!(Json::parse("1.5").unwrap().as_i64(), None);
+        assert_eq!(Json::parse("1e16").unwrap().as_i64(), None);
+        assert_eq!(Json::parse("\"1\"").unwrap().as_i64(), None);
+    }
+
+    #[test]
+    fn decodes_escapes() {
+        assert_eq!(string(r#""\"\\\/\b\f\n\r\t""#), "\"\\/\u{8}\u{c}\n\r\t");
+        assert_eq!(string(r#""\u0041\u00e9\u4E2D""#), "A\u{e9}\u{4e2d}");
+        assert_eq!(string("\"é中😀\""), "é中😀");
+    }
+
+    #[test]
+    fn decodes_surrogate_pairs() {
+        assert_eq!(string(r#""\ud83d\ude00""#), "\u{1f600}");
+        assert_eq!(string(r#""\uD834\uDD1E!""#), "\u{1d11e}!");
+        // unpaired halves can't be represented and become U+FFFD
+        assert_eq!(string(r#""\ud800""#), "\u{fffd}");
+        assert_eq!(string(r#""\udc00x""#), "\u{fffd}x");
+        assert_eq!(string(r#""\ud800\u0041""#), "\u{fffd}A");
+        assert_eq!(string(r#""\ud800\ud800\udc00""#), "\u{fffd}\u{10000}");
+    }
+
+    #[test]
+    fn round_trips_through_display() {
+        let value = Json::Object(vec![
+            ("text".to_string(), "quote \" backslash \\ newline \n tab \t bell \u{7} 😀".into()),
+            ("items".to_string(), Json::Array(vec![Json::Null, true.into(), 12.into(), Json::Number(-0.25)])),
+            ("nested".to_string(), Json::Object(vec![("empty".to_string(), Json::Array(Vec::new()))])),
+        ]);
+        let text = value.to_string();
+        assert!(!text.contains('\n') && !text.contains('\u{7}'));
+        assert_eq!(Json::parse(&text), Ok(value));
+        assert_eq!(Json::Number(f64::NAN).to_string(), "null");
+    }
+
+    #[test]
+    fn limits_nesting() {
+        let nested = |depth: usize| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(Json::parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(Json::parse(&nested(MAX_DEPTH + 1)).unwrap_err().starts_with("nested more than"));
+        let objects = "{\"a\":".repeat(MAX_DEPTH + 1) + "1" + &"}".repeat(MAX_DEPTH + 1);
+        assert!(Json::parse(&objects).unwrap_err().starts_with("nested more than"));
+        // the depth unwinds, so siblings may each go as deep as the limit
+        let siblings = format!("[{},{}]", nested(MAX_DEPTH - 1), nested(MAX_DEPTH - 1));
+        assert!(Json::parse(&siblings).is_ok());
+    }
+
+    #[test]
+    fn rejects_malformed_text() {
+        for text in [
+            "", "  ", "{", "[1,]", "[1 2]", "{\"a\" 1}", "{\"a\":1,}", "{a:1}", "{\"a\":1} x",
+            "\"open", r#""\x""#, r#""\u12""#, r#""\u12g4""#, "tru", "nul", "-", "1.2.3", "+1", "'a'",
+        ] {
+            assert!(Json::parse(text).is_err(), "{:?} should not parse", text);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! The simulator behind the life-rs viewer: a Game of Life engine, pattern
+//! file I/O, a library of well-known patterns and tools for analysing runs.
+//! The window and its config file are behind the `viewer` feature, which is
+//! on by default; the command line and the headless modes build without it.
+//!
+//! ```
+//! use life_rs::{analysis, pattern_library, World};
+//!
+//! let mut world = World::new((64, 64));
+//! let glider = pattern_library::find("glider").unwrap().pattern();
+//! glider.place(&mut world, (10, 10));
+//! let summary = analysis::simulate(&mut world, 0, Some(4), false);
+//! assert_eq!(summary.population, 5);
+//! ```
+#![warn(missing_docs)]
+
+/// The two-state engine: [`World`] and its cells.
+pub mod life_cell;
+/// Birth/survival rules for the two-state engine.
+pub mod rule;
+/// Reproducible random fills.
+pub mod soup;
+/// Running a world until it stops or settles, and summarising the result.
+pub mod analysis;
+/// Writing the generations of a run out as they happen.
+pub mod stream;
+/// Conditions that stop a run when they come true.
+pub mod condition;
+/// A common interface over the simulation backends.
+pub mod engine;
+/// Standard workloads for timing the engines.
+pub mod benchmark;
+/// Checking the engines against each other.
+pub mod verify;
+
+/// Patterns and reading and writing them in the common file formats.
+pub mod pattern;
+mod rle;
+mod plaintext;
+mod life105;
+mod life106;
+mod macrocell;
+/// Well-known patterns built into the crate.
+pub mod pattern_library;
+/// Saving and restoring a running simulation.
+pub mod session;
+
+/// Multi-state cellular automata, for rules loaded from Golly rule files.
+pub mod multi_state;
+/// The @TABLE engine for Golly rule files.
+pub mod rule_table;
+/// The @TREE engine for Golly rule files.
+pub mod rule_tree;
+/// Loading Golly `.rule` files.
+pub mod rule_file;
+
+/// A small JSON value type with a parser and a writer.
+pub mod json;
+/// The commands the console and the remote control accept.
+pub mod command;
+/// Taking commands over a localhost socket.
+pub mod remote;
+/// Marking where cells recently died.
+pub mod trail;
+/// The command line of the viewer and the headless modes.
+pub mod cli;
+mod direction;
+
+#[cfg(feature = "viewer")]
+mod input_role;
+#[cfg(feature = "viewer")]
+mod bgchar_data;
+#[cfg(feature = "viewer")]
+mod bgpal_data;
+#[cfg(feature = "viewer")]
+mod game_window;
+#[cfg(feature = "viewer")]
+mod wait_and_update;
+#[cfg(feature = "viewer")]
+mod console;
+/// The viewer's config.ini.
+#[cfg(feature = "viewer")]
+pub mod config;
+/// The viewer window and its main loop.
+#[cfg(feature = "viewer")]
+pub mod viewer;
+
+pub use life_cell::{
+    Cell,
+    CellState,
+    World,
+};
+pub use rule::Rule;
+pub use pattern::{
+    Format,
+    Pattern,
+    PatternError,
+};
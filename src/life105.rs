@@ -0,0 +1,155 @@
+use crate::{
+    pattern::*,
+    rule::*,
+};
+
+pub const HEADER: &str = "#Life 1.05";
+
+pub fn parse(text: &str) -> Result<Pattern, PatternError> {
+    let mut pattern = Pattern::default();
+    let mut cells = Vec::new();
+    // the rows are counted from the last #P line
+    let (mut block_x, mut block_y, mut row) = (0isize, 0isize, 0usize);
+    let mut header = false;
+    for (idx, line) in text.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = line.trim();
+        // leading blank lines are skipped, as Format::detect does
+        if !header {
+            if line.is_empty() {
+                continue;
+            }
+            if line != HEADER {
+                return Err(PatternError::syntax(line_no, format!("expected \"{}\" header", HEADER)));
+            }
+            header = true;
+            continue;
+        }
+        if let Some(directive) = line.strip_prefix('#') {
+            let (tag, body) = directive.split_at(directive.chars().next().map_or(0, |c| c.len_utf8()));
+            let body = body.trim();
+            match tag {
+                "D" | "C" => pattern.comments.push(body.to_string()),
+                "N" => pattern.rule = Some(Rule::default()),
+                "R" => {
+                    pattern.rule = Some(Rule::parse(body)
+                        .ok_or_else(|| PatternError::syntax(line_no, format!("unsupported rule \"{}\"", body)))?);
+                }
+                "P" => {
+                    let position: Vec<_> = body.split_whitespace().map(|n| n.parse::<isize>()).collect();
+                    match position[..] {
+                        [Ok(x), Ok(y)] => (block_x, block_y, row) = (x, y, 0),
+                        _ => return Err(PatternError::syntax(line_no, format!("malformed block position \"{}\"", body))),
+                    }
+                }
+                _ => return Err(PatternError::syntax(line_no, format!("unknown directive \"#{}\"", tag))),
+            }
+            continue;
+        }
+        for (column, c) in line.chars().enumerate() {
+            match c {
+                '.' => {}
+                '*' => {
+                    let x = isize::try_from(column).ok().and_then(|column| block_x.checked_add(column));
+                    let y = isize::try_from(row).ok().and_then(|row| block_y.checked_add(row));
+                    let (Some(x), Some(y)) = (x, y) else {
+                        return Err(PatternError::syntax(line_no, format!("cell at column {} lies beyond the largest coordinate", column + 1)));
+                    };
+                    cells.push((x, y));
+                }
+                c => return Err(PatternError::syntax(line_no, format!("unexpected character '{}' at column {}", c, column + 1))),
+            }
+        }
+        row += 1;
+    }
+    if !header {
+        return Err(PatternError::syntax(text.lines().count().max(1), format!("expected \"{}\" header", HEADER)));
+    }
+    pattern.set_signed_cells(&cells)?;
+    Ok(pattern)
+}
+
+pub fn write(pattern: &Pattern) -> String {
+    let mut text = format!("{}\n", HEADER);
+    for comment in pattern.name.iter().chain(pattern.author.iter()).chain(pattern.comments.iter()) {
+        text += &format!("#D {}\n", comment);
+    }
+    match pattern.rule {
+        Some(rule) if rule != Rule::default() => {
+            let survival: String = (0..=8).filter(|&n| rule.survival(n)).map(|n| n.to_string()).collect();
+            let birth: String = (0..=8).filter(|&n| rule.birth(n)).map(|n| n.to_string()).collect();
+            text += &format!("#R {}/{}\n", survival, birth);
+        }
+        _ => text += "#N\n",
+    }
+    text += &format!("#P {} {}\n", -(pattern.size.0 as isize / 2), -(pattern.size.1 as isize / 2));
+    for row in pattern.rows() {
+        let width = row.iter().rposition(|&live| live).map_or(0, |x| x + 1);
+        if width == 0 {
+            text.push('.');
+        }
+        text.extend(row[..width].iter().map(|&live| if live { '*' } else { '.' }));
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn syntax_line(result: Result<Pattern, PatternError>) -> usize {
+        match result {
+            Err(PatternError::Syntax { line, .. }) => line,
+            other => panic!("expected a syntax error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_blocks_at_their_positions() {
+        let pattern = parse("#Life 1.05\n#D two blocks\n#N\n#P -1 -1\n**\n**\n#P 3 2\n.*\n").unwrap();
+        assert_eq!(pattern.comments, ["two blocks"]);
+        assert_eq!(pattern.rule, Some(Rule::default()));
+        assert_eq!(pattern.size, (6, 4));
+        assert_eq!(pattern.cells, [(0, 0), (1, 0), (0, 1), (1, 1), (5, 3)]);
+    }
+
+    #[test]
+    fn skips_blank_lines_before_the_header() {
+        let text = "\n#Life 1.05\n*\n";
+        assert_eq!(Format::detect(text), Format::Life105);
+        assert_eq!(parse(text).unwrap().cells, [(0, 0)]);
+        assert_eq!(syntax_line(parse("\n\n")), 2);
+    }
+
+    #[test]
+    fn reads_the_rule_as_survival_then_birth() {
+        let pattern = parse("#Life 1.05\n#R 23/36\n*\n").unwrap();
+        assert_eq!(pattern.rule, Rule::parse("B36/S23"));
+    }
+
+    #[test]
+    fn round_trips_through_write() {
+        for rule in ["B3/S23", "B36/S23"] {
+            let mut pattern = parse("#Life 1.05\n#P 0 0\n.*\n\n***\n").unwrap();
+            pattern.rule = Rule::parse(rule);
+            let parsed = parse(&write(&pattern)).unwrap();
+            assert_eq!(parsed.rule, pattern.rule);
+            assert_eq!(parsed.size, pattern.size);
+            assert_eq!(parsed.cells, pattern.cells);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert_eq!(syntax_line(parse("#Life 1.06\n0 0\n")), 1);
+        assert_eq!(syntax_line(parse("#Life 1.05\n#R 23/9\n")), 2);
+        assert_eq!(syntax_line(parse("#Life 1.05\n#P 1\n")), 2);
+        assert_eq!(syntax_line(parse("#Life 1.05\n#X\n")), 2);
+        assert_eq!(syntax_line(parse("#Life 1.05\n#P 0 0\n*.\n*o\n")), 4);
+        assert_eq!(syntax_line(parse("#Life 1.05\n#P 9223372036854775807 0\n.*\n")), 3);
+        assert_eq!(syntax_line(parse("#Life 1.05\n#P 0 9223372036854775807\n*\n*\n")), 4);
+        let result = parse("#Life 1.05\n#P -9223372036854775808 0\n*\n#P 9223372036854775807 0\n*\n");
+        assert!(matches!(result, Err(PatternError::TooLarge { .. })), "{:?}", result);
+    }
+}
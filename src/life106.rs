@@ -0,0 +1,88 @@
+use crate::pattern::*;
+
+pub const HEADER: &str = "#Life 1.06";
+
+pub fn parse(text: &str) -> Result<Pattern, PatternError> {
+    let mut pattern = Pattern::default();
+    let mut cells = Vec::new();
+    let mut header = false;
+    for (idx, line) in text.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        // leading blank lines are skipped, as Format::detect does
+        if !header {
+            if line != HEADER {
+                return Err(PatternError::syntax(line_no, format!("expected \"{}\" header", HEADER)));
+            }
+            header = true;
+            continue;
+        }
+        let position: Vec<_> = line.split_whitespace().map(|n| n.parse::<isize>()).collect();
+        match position[..] {
+            [Ok(x), Ok(y)] => cells.push((x, y)),
+            _ => return Err(PatternError::syntax(line_no, format!("expected \"x y\" but found \"{}\"", line))),
+        }
+    }
+    if !header {
+        return Err(PatternError::syntax(text.lines().count().max(1), format!("expected \"{}\" header", HEADER)));
+    }
+    pattern.set_signed_cells(&cells)?;
+    Ok(pattern)
+}
+
+pub fn write(pattern: &Pattern) -> String {
+    let mut text = format!("{}\n", HEADER);
+    let (ox, oy) = (pattern.size.0 as isize / 2, pattern.size.1 as isize / 2);
+    for &(x, y) in &pattern.cells {
+        text += &format!("{} {}\n", x as isize - ox, y as isize - oy);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_signed_coordinates() {
+        let pattern = parse("#Life 1.06\n0 -1\n1 0\n\n-1 1\n0 1\n1 1\n").unwrap();
+        assert_eq!(pattern.size, (3, 3));
+        assert_eq!(pattern.cells, [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn skips_blank_lines_before_the_header() {
+        let text = "\n  \n#Life 1.06\n0 0\n";
+        assert_eq!(Format::detect(text), Format::Life106);
+        assert_eq!(parse(text).unwrap().cells, [(0, 0)]);
+    }
+
+    #[test]
+    fn rejects_coordinates_too_far_apart() {
+        let result = parse("#Life 1.06\n-9223372036854775808 0\n9223372036854775807 0\n");
+        assert!(matches!(result, Err(PatternError::TooLarge { .. })), "{:?}", result);
+        let pattern = parse("#Life 1.06\n-4611686018427387904 0\n4611686018427387902 0\n").unwrap();
+        assert_eq!(pattern.size, (MAX_PATTERN_SIZE, 1));
+    }
+
+    #[test]
+    fn round_trips_through_write() {
+        let pattern = parse("#Life 1.06\n5 5\n6 5\n7 5\n5 9\n").unwrap();
+        let parsed = parse(&write(&pattern)).unwrap();
+        assert_eq!(parsed.size, pattern.size);
+        assert_eq!(parsed.cells, pattern.cells);
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        for (text, line_no) in [("0 0\n", 1), ("\n\n", 2), ("#Life 1.06\n0 0\n1\n", 3), ("#Life 1.06\n0 x\n", 2), ("#Life 1.06\n0 0 0\n", 2)] {
+            match parse(text) {
+                Err(PatternError::Syntax { line, .. }) => assert_eq!(line, line_no, "{:?}", text),
+                other => panic!("expected a syntax error for {:?}, got {:?}", text, other),
+            }
+        }
+    }
+}
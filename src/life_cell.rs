@@ -1,6 +1,14 @@
-#[derive(Clone, Copy, PartialEq)]
+use crate::{
+    multi_state::*,
+    rule::*,
+};
+
+/// The state of a single cell.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub enum CellState {
+    /// An empty cell.
     Dead,
+    /// An occupied cell.
     Live,
 }
 impl Default for CellState {
@@ -9,82 +17,314 @@ impl Default for CellState {
     }
 }
 impl CellState {
+    /// Returns the opposite state.
     pub fn invert(self) -> Self {
         if self == Self::Dead { Self::Live } else { Self::Dead }
     }
 }
 
-#[derive(Default, Clone, Copy)]
+/// A cell as read back from a [`World`].
+#[derive(Default, Debug, Clone, Copy)]
 pub struct Cell {
+    /// Whether the cell is alive.
     pub state: CellState,
+    /// How many of its eight neighbours are alive.
     pub neighbours: i32,
 }
 
+const LIVE_BIT: u8 = 0x10;
+const NEIGHBOURS_MASK: u8 = 0x0f;
+
+/// The smallest world width and height in cells.
+pub const MIN_WORLD_SIZE: (usize, usize) = (16, 16);
+/// The largest world width or height in cells.
+pub const MAX_WORLD_SIZE: usize = 1 << 14;
+
+// packed cell (LIVE_BIT | neighbours) -> whether it flips in the next generation
+fn flip_tbl(rule: Rule) -> [bool; 32] {
+    let mut tbl = [false; 32];
+    for neighbours in 0..=8 {
+        tbl[neighbours] = rule.birth(neighbours);
+        tbl[LIVE_BIT as usize + neighbours] = !rule.survival(neighbours);
+    }
+    tbl
+}
+
+/// A grid with wrapped edges running a two-state outer-totalistic rule (B3/S23 by default).
+///
+/// The cells form one ring, row after row: the top and bottom edges meet as on
+/// a torus, but the cell left of a row's first cell is the last cell of the row
+/// above, and the cell right of its last cell is the first of the row below.
+///
+/// Each cell is packed into a byte holding its state and live neighbour count.
+/// A generation scans the whole grid in 64-cell chunks, skipping chunks with
+/// no live cells or neighbours, then flips the cells that change and updates
+/// their neighbours' counts.
 pub struct World {
     size: (usize, usize),
     linear_size: usize,
-    grid: Vec<Cell>,
+    grid: Vec<u8>,
     changes: Vec<usize>,
+    rule: Rule,
+    flip_tbl: [bool; 32],
 }
 impl World {
+    /// Creates an empty world of `size` cells (width, height).
     pub fn new(size: (usize, usize)) -> Self {
         let linear_size = size.0 * size.1;
         Self {
             size,
             linear_size,
-            grid: vec![Cell::default(); linear_size],
-            changes: Vec::with_capacity(linear_size / 8),
+            grid: vec![0; linear_size],
+            changes: Vec::new(),
+            rule: Rule::default(),
+            flip_tbl: flip_tbl(Rule::default()),
         }
     }
 
+    /// The rule the world is running.
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    /// Switches the world to `rule` from the next generation on.
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+        self.flip_tbl = flip_tbl(rule);
+    }
+
+    /// The world size in cells (width, height).
+    pub fn size(&self) -> (usize, usize) {
+        self.size
+    }
+
+    /// Reads the cell at `pos`; coordinates wrap around the edges.
     pub fn read_cell(&self, pos: (usize, usize)) -> Cell {
         let linear_pos = (pos.0 % self.size.0) + (pos.1 % self.size.1) * self.size.0;
-        self.grid[linear_pos]
+        let packed = self.grid[linear_pos];
+        Cell {
+            state: if packed & LIVE_BIT != 0 { CellState::Live } else { CellState::Dead },
+            neighbours: (packed & NEIGHBOURS_MASK) as i32,
+        }
     }
 
     fn change_cell(&mut self, linear_pos: usize) -> i32 {
-        self.grid[linear_pos].state = self.grid[linear_pos].state.invert();
-        let d = if self.grid[linear_pos].state == CellState::Live { 1 } else { -1 };
+        self.grid[linear_pos] ^= LIVE_BIT;
+        let (d, packed_d) = if self.grid[linear_pos] & LIVE_BIT != 0 { (1, 1) } else { (-1, u8::MAX) };
         let temp_pos_b = linear_pos + self.linear_size;
         let temp_pos_a = temp_pos_b - self.size.0;
         let temp_pos_c = temp_pos_b + self.size.0;
-        self.grid[(temp_pos_a - 1) % self.linear_size].neighbours += d;
-        self.grid[(temp_pos_a    ) % self.linear_size].neighbours += d;
-        self.grid[(temp_pos_a + 1) % self.linear_size].neighbours += d;
-        self.grid[(temp_pos_b - 1) % self.linear_size].neighbours += d;
-        self.grid[(temp_pos_b + 1) % self.linear_size].neighbours += d;
-        self.grid[(temp_pos_c - 1) % self.linear_size].neighbours += d;
-        self.grid[(temp_pos_c    ) % self.linear_size].neighbours += d;
-        self.grid[(temp_pos_c + 1) % self.linear_size].neighbours += d;
+        for neighbour_pos in [
+            temp_pos_a - 1, temp_pos_a, temp_pos_a + 1,
+            temp_pos_b - 1,             temp_pos_b + 1,
+            temp_pos_c - 1, temp_pos_c, temp_pos_c + 1,
+        ] {
+            let packed = &mut self.grid[neighbour_pos % self.linear_size];
+            *packed = packed.wrapping_add(packed_d);
+        }
         d
     }
 
+    /// Sets the cell at `pos` and returns the change in population (-1, 0 or 1).
+    /// An edit clears [`World::changes`], which no longer lead to these cells.
     pub fn set_cell(&mut self, pos: (usize, usize), state: CellState) -> i32 {
         let linear_pos = (pos.0 % self.size.0) + (pos.1 % self.size.1) * self.size.0;
-        if self.grid[linear_pos].state != state {
+        self.changes.clear();
+        if self.read_cell(pos).state != state {
             self.change_cell(linear_pos)
         } else {
             0
         }
     }
 
+    /// Advances the world by one generation and returns the change in population.
     pub fn update_world(&mut self) -> i32 {
         let mut growth = 0;
-        for (linear_pos, cell) in self.grid.iter_mut().enumerate() {
-            if cell.state == CellState::Live {
-                match cell.neighbours {
-                    2 | 3 => {},
-                    _ => self.changes.push(linear_pos),
-                }
-            } else {
-                if cell.neighbours == 3 {
-                    self.changes.push(linear_pos);
+        self.changes.clear();
+        for (chunk_no, chunk) in self.grid.chunks(64).enumerate() {
+            if chunk.iter().all(|&packed| packed == 0) && !self.flip_tbl[0] {
+                continue;
+            }
+            for (idx, &packed) in chunk.iter().enumerate() {
+                if self.flip_tbl[packed as usize] {
+                    self.changes.push(chunk_no * 64 + idx);
                 }
             }
         }
-        while let Some(linear_pos) = self.changes.pop() {
+        let changes = std::mem::take(&mut self.changes);
+        for &linear_pos in &changes {
             growth += self.change_cell(linear_pos);
         }
+        self.changes = changes;
+        growth
+    }
+
+    /// Iterates over the positions of the live cells, row by row.
+    pub fn live_cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.grid.iter().enumerate()
+            .filter(|(_, &packed)| packed & LIVE_BIT != 0)
+            .map(|(linear_pos, _)| (linear_pos % self.size.0, linear_pos / self.size.0))
+    }
+
+    /// The smallest rectangle (position, size) holding every live cell,
+    /// or `None` if the world is empty.
+    pub fn bounding_box(&self) -> Option<((usize, usize), (usize, usize))> {
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+        for (x, y) in self.live_cells() {
+            bounds = Some(match bounds {
+                Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                None => (x, y, x, y),
+            });
+        }
+        bounds.map(|(x0, y0, x1, y1)| ((x0, y0), (x1 - x0 + 1, y1 - y0 + 1)))
+    }
+
+    /// Returns a copy of the world at a new `size`, with every live cell moved by `offset`.
+    /// Cells that land outside the new world are dropped.
+    pub fn resized(&self, size: (usize, usize), offset: (isize, isize)) -> Self {
+        let mut world = Self::new(size);
+        world.set_rule(self.rule);
+        for (x, y) in self.live_cells() {
+            let (x, y) = (x as isize + offset.0, y as isize + offset.1);
+            if x >= 0 && y >= 0 && (x as usize) < size.0 && (y as usize) < size.1 {
+                world.set_cell((x as usize, y as usize), CellState::Live);
+            }
+        }
+        world
+    }
+
+    /// Makes the cells that are not in state 0 in `grid` the live ones and
+    /// returns the change in population. The flips are recorded as
+    /// [`World::changes`], as if a generation had been run, so a world can
+    /// follow a multi-state run. `grid` must be the same size.
+    pub fn follow(&mut self, grid: &StateGrid) -> i32 {
+        let mut growth = 0;
+        self.changes.clear();
+        for linear_pos in 0..self.linear_size {
+            let pos = (linear_pos % self.size.0, linear_pos / self.size.0);
+            if (grid.read_cell(pos) != 0) != (self.grid[linear_pos] & LIVE_BIT != 0) {
+                growth += self.change_cell(linear_pos);
+                self.changes.push(linear_pos);
+            }
+        }
         growth
     }
+
+    /// The cells that flipped in the last call to [`World::update_world`] or
+    /// [`World::follow`], or none if a cell has been set since.
+    pub fn changes(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.changes.iter().map(|linear_pos| (linear_pos % self.size.0, linear_pos / self.size.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::soup::*;
+
+    // live neighbours of `pos` in a plain grid, wrapped as one ring like World
+    fn neighbours(cells: &[bool], width: usize, pos: usize) -> usize {
+        let (len, width) = (cells.len() as isize, width as isize);
+        [-width - 1, -width, -width + 1, -1, 1, width - 1, width, width + 1].iter()
+            .filter(|&&offset| cells[(pos as isize + offset).rem_euclid(len) as usize])
+            .count()
+    }
+
+    // one generation the simple way, to check the packed grid against
+    fn reference_step(cells: &[bool], width: usize, rule: Rule) -> Vec<bool> {
+        (0..cells.len()).map(|pos| {
+            let count = neighbours(cells, width, pos);
+            if cells[pos] { rule.survival(count) } else { rule.birth(count) }
+        }).collect()
+    }
+
+    fn cells(world: &World) -> Vec<bool> {
+        let (width, height) = world.size();
+        (0..width * height).map(|pos| world.read_cell((pos % width, pos / width)).state == CellState::Live).collect()
+    }
+
+    fn population(cells: &[bool]) -> i32 {
+        cells.iter().filter(|&&live| live).count() as i32
+    }
+
+    // checks the cells, their neighbour counts, the growth and the changes after `generations`
+    fn check_against_reference(world: &mut World, generations: usize) {
+        let width = world.size().0;
+        let mut expected = cells(world);
+        for generation in 1..=generations {
+            let next = reference_step(&expected, width, world.rule());
+            let growth = world.update_world();
+            assert_eq!(cells(world), next, "generation {}", generation);
+            assert_eq!(growth, population(&next) - population(&expected), "generation {}", generation);
+            for pos in 0..next.len() {
+                assert_eq!(world.read_cell((pos % width, pos / width)).neighbours as usize, neighbours(&next, width, pos));
+            }
+            let flipped: Vec<_> = (0..next.len()).filter(|&pos| next[pos] != expected[pos]).map(|pos| (pos % width, pos / width)).collect();
+            assert_eq!(world.changes().collect::<Vec<_>>(), flipped, "generation {}", generation);
+            expected = next;
+        }
+    }
+
+    // 20x17 leaves the last 64-cell chunk part full
+    const SIZE: (usize, usize) = (20, 17);
+
+    #[test]
+    fn glider_crosses_the_wrapped_edges() {
+        let mut world = World::new(SIZE);
+        for pos in [(18, 14), (19, 15), (17, 16), (18, 16), (19, 16)] {
+            world.set_cell(pos, CellState::Live);
+        }
+        check_against_reference(&mut world, 160);
+        assert_eq!(world.live_cells().count(), 5);
+    }
+
+    #[test]
+    fn matches_the_reference_on_a_soup() {
+        let mut world = World::new(SIZE);
+        Soup::new(7, 0.4, Symmetry::C1).fill(&mut world, ((0, 0), SIZE));
+        check_against_reference(&mut world, 60);
+    }
+
+    #[test]
+    fn b0_rule_runs_empty_chunks() {
+        let mut world = World::new(SIZE);
+        world.set_rule(Rule::parse("B0123478/S01234678").unwrap());
+        check_against_reference(&mut world, 1);
+        assert_eq!(world.live_cells().count(), SIZE.0 * SIZE.1);
+        check_against_reference(&mut world, 1);
+        Soup::new(3, 0.5, Symmetry::C1).fill(&mut world, ((0, 0), (10, 8)));
+        check_against_reference(&mut world, 20);
+    }
+
+    #[test]
+    fn edits_clear_the_changes() {
+        let mut world = World::new(SIZE);
+        Soup::new(9, 0.4, Symmetry::C1).fill(&mut world, ((0, 0), SIZE));
+        world.update_world();
+        assert!(world.changes().next().is_some());
+        world.set_cell((0, 0), world.read_cell((0, 0)).state);
+        assert_eq!(world.changes().count(), 0);
+    }
+
+    #[test]
+    fn follow_records_the_flips() {
+        let mut world = World::new(SIZE);
+        Soup::new(5, 0.5, Symmetry::C1).fill(&mut world, ((0, 0), SIZE));
+        let before = cells(&world);
+        let mut grid = StateGrid::new(SIZE);
+        for pos in (0..SIZE.0 * SIZE.1).filter(|pos| pos % 3 == 0) {
+            grid.set_cell((pos % SIZE.0, pos / SIZE.0), (pos % 4) as u8);
+        }
+        let growth = world.follow(&grid);
+        let after = cells(&world);
+        let width = SIZE.0;
+        for pos in 0..after.len() {
+            assert_eq!(after[pos], grid.read_cell((pos % width, pos / width)) != 0);
+            assert_eq!(world.read_cell((pos % width, pos / width)).neighbours as usize, neighbours(&after, width, pos));
+        }
+        assert_eq!(growth, population(&after) - population(&before));
+        let flipped: Vec<_> = (0..after.len()).filter(|&pos| after[pos] != before[pos]).map(|pos| (pos % width, pos / width)).collect();
+        assert!(!flipped.is_empty());
+        assert_eq!(world.changes().collect::<Vec<_>>(), flipped);
+    }
 }
@@ -0,0 +1,368 @@
+use std::collections::HashMap;
+use crate::{
+    life_cell::MAX_WORLD_SIZE,
+    pattern::*,
+    rule::*,
+};
+
+pub const HEADER: &str = "[M2]";
+
+const LEAF_LEVEL: u32 = 3;
+const LEAF_SIZE: usize = 1 << LEAF_LEVEL;
+// a pattern bigger than this fits in no world, and one with more live cells
+// than this is refused before it is expanded into a list of cells
+const MAX_EXPANDED_SIZE: u64 = MAX_WORLD_SIZE as u64;
+const MAX_EXPANDED_CELLS: u64 = 1 << 22;
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+enum Node {
+    Leaf([u8; LEAF_SIZE]),
+    Branch(u32, [usize; 4]),
+}
+
+impl Node {
+    fn level(&self) -> u32 {
+        match self {
+            Self::Leaf(_) => LEAF_LEVEL,
+            Self::Branch(level, _) => *level,
+        }
+    }
+}
+
+pub fn parse(text: &str) -> Result<Pattern, PatternError> {
+    let mut pattern = Pattern::default();
+    // node 0 is the empty node of any level
+    let mut nodes: Vec<Option<Node>> = vec![None];
+    let mut header = false;
+    let mut last_line_no = 1;
+    for (idx, line) in text.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        // leading blank lines are skipped, as Format::detect does
+        if !header {
+            if !line.starts_with(HEADER) {
+                return Err(PatternError::syntax(line_no, format!("expected \"{}\" header", HEADER)));
+            }
+            header = true;
+            continue;
+        }
+        last_line_no = line_no;
+        if let Some(directive) = line.strip_prefix('#') {
+            let (tag, body) = directive.split_at(directive.chars().next().map_or(0, |c| c.len_utf8()));
+            let body = body.trim();
+            match tag {
+                "R" => {
+                    pattern.rule = Some(Rule::parse(body)
+                        .ok_or_else(|| PatternError::syntax(line_no, format!("unsupported rule \"{}\"", body)))?);
+                }
+                "N" => pattern.name = Some(body.to_string()),
+                "O" => pattern.author = Some(body.to_string()),
+                "C" => pattern.comments.push(body.to_string()),
+                _ => {}
+            }
+            continue;
+        }
+        let node = if line.starts_with(|c: char| c.is_ascii_digit()) {
+            parse_branch(line_no, line, &nodes)?
+        } else {
+            parse_leaf(line_no, line)?
+        };
+        nodes.push(Some(node));
+    }
+    if !header {
+        return Err(PatternError::syntax(text.lines().count().max(1), format!("expected \"{}\" header", HEADER)));
+    }
+    let Some(&Some(root)) = nodes.last() else {
+        pattern.size = (0, 0);
+        return Ok(pattern);
+    };
+
+    let mut bounds = vec![None; nodes.len()];
+    let mut populations = vec![0u64; nodes.len()];
+    for id in 1..nodes.len() {
+        bounds[id] = node_bounds(&nodes, &bounds, id);
+        populations[id] = match nodes[id] {
+            Some(Node::Leaf(rows)) => rows.iter().map(|row| row.count_ones() as u64).sum(),
+            Some(Node::Branch(_, children)) => children.iter().fold(0, |sum: u64, &child| sum.saturating_add(populations[child])),
+            None => 0,
+        };
+    }
+    if populations[nodes.len() - 1] > MAX_EXPANDED_CELLS {
+        return Err(PatternError::syntax(last_line_no, format!("more than {} live cells", MAX_EXPANDED_CELLS)));
+    }
+    if let Some((x0, y0, x1, y1)) = bounds[nodes.len() - 1] {
+        if x1 - x0 >= MAX_EXPANDED_SIZE || y1 - y0 >= MAX_EXPANDED_SIZE {
+            return Err(PatternError::TooLarge {
+                size: ((x1 - x0 + 1) as usize, (y1 - y0 + 1) as usize),
+                limit: (MAX_EXPANDED_SIZE as usize, MAX_EXPANDED_SIZE as usize),
+            });
+        }
+        let mut cells = Vec::new();
+        collect_cells(&nodes, nodes.len() - 1, (-(x0 as i64), -(y0 as i64)), root.level(), &mut cells);
+        pattern.set_signed_cells(&cells)?;
+    }
+    Ok(pattern)
+}
+
+fn parse_leaf(line_no: usize, line: &str) -> Result<Node, PatternError> {
+    let mut rows = [0u8; LEAF_SIZE];
+    let (mut x, mut y) = (0, 0);
+    for (column, c) in line.chars().enumerate() {
+        match c {
+            '.' | '*' => {
+                if x >= LEAF_SIZE || y >= LEAF_SIZE {
+                    return Err(PatternError::syntax(line_no, format!("leaf cell outside 8x8 at column {}", column + 1)));
+                }
+                if c == '*' {
+                    rows[y] |= 1 << x;
+                }
+                x += 1;
+            }
+            '$' => {
+                x = 0;
+                y += 1;
+            }
+            c => return Err(PatternError::syntax(line_no, format!("unexpected character '{}' at column {}", c, column + 1))),
+        }
+    }
+    Ok(Node::Leaf(rows))
+}
+
+fn parse_branch(line_no: usize, line: &str, nodes: &[Option<Node>]) -> Result<Node, PatternError> {
+    let numbers: Vec<_> = line.split_whitespace().map(|n| n.parse::<usize>()).collect();
+    let [Ok(level), Ok(nw), Ok(ne), Ok(sw), Ok(se)] = numbers[..] else {
+        return Err(PatternError::syntax(line_no, format!("expected \"level nw ne sw se\" but found \"{}\"", line)));
+    };
+    if level <= LEAF_LEVEL as usize || level > 62 {
+        return Err(PatternError::syntax(line_no, format!("unsupported node level {} (two-state macrocells use 8x8 leaves)", level)));
+    }
+    for child in [nw, ne, sw, se] {
+        match nodes.get(child) {
+            Some(None) => {}
+            Some(Some(node)) if node.level() as usize == level - 1 => {}
+            Some(Some(_)) => return Err(PatternError::syntax(line_no, format!("node {} is not of level {}", child, level - 1))),
+            None => return Err(PatternError::syntax(line_no, format!("reference to undefined node {}", child))),
+        }
+    }
+    Ok(Node::Branch(level as u32, [nw, ne, sw, se]))
+}
+
+fn node_bounds(nodes: &[Option<Node>], bounds: &[Option<(u64, u64, u64, u64)>], id: usize) -> Option<(u64, u64, u64, u64)> {
+    match nodes[id]? {
+        Node::Leaf(rows) => {
+            let live_rows: Vec<_> = (0..LEAF_SIZE as u64).filter(|&y| rows[y as usize] != 0).collect();
+            let columns = rows.iter().fold(0u8, |acc, row| acc | row);
+            Some((
+                columns.trailing_zeros() as u64,
+                *live_rows.first()?,
+                7 - columns.leading_zeros() as u64,
+                *live_rows.last()?,
+            ))
+        }
+        Node::Branch(level, children) => {
+            let half = 1u64 << (level - 1);
+            let mut result: Option<(u64, u64, u64, u64)> = None;
+            for (quadrant, &child) in children.iter().enumerate() {
+                if let Some((x0, y0, x1, y1)) = bounds[child] {
+                    let (dx, dy) = ((quadrant as u64 & 1) * half, (quadrant as u64 >> 1) * half);
+                    let (x0, y0, x1, y1) = (x0 + dx, y0 + dy, x1 + dx, y1 + dy);
+                    result = Some(match result {
+                        Some((rx0, ry0, rx1, ry1)) => (rx0.min(x0), ry0.min(y0), rx1.max(x1), ry1.max(y1)),
+                        None => (x0, y0, x1, y1),
+                    });
+                }
+            }
+            result
+        }
+    }
+}
+
+fn collect_cells(nodes: &[Option<Node>], id: usize, pos: (i64, i64), level: u32, cells: &mut Vec<(isize, isize)>) {
+    match nodes[id] {
+        None => {}
+        Some(Node::Leaf(rows)) => {
+            for (y, row) in rows.iter().enumerate() {
+                for x in (0..LEAF_SIZE).filter(|x| row & 1 << x != 0) {
+                    cells.push(((pos.0 + x as i64) as isize, (pos.1 + y as i64) as isize));
+                }
+            }
+        }
+        Some(Node::Branch(_, children)) => {
+            let half = 1i64 << (level - 1);
+            for (quadrant, &child) in children.iter().enumerate() {
+                let (dx, dy) = ((quadrant as i64 & 1) * half, (quadrant as i64 >> 1) * half);
+                collect_cells(nodes, child, (pos.0 + dx, pos.1 + dy), level - 1, cells);
+            }
+        }
+    }
+}
+
+pub fn write(pattern: &Pattern) -> String {
+    let mut text = format!("{} (life-rs {})\n", HEADER, env!("CARGO_PKG_VERSION"));
+    text += &format!("#R {}\n", pattern.rule.unwrap_or_default());
+    if let Some(name) = &pattern.name {
+        text += &format!("#N {}\n", name);
+    }
+    if let Some(author) = &pattern.author {
+        text += &format!("#O {}\n", author);
+    }
+    for comment in &pattern.comments {
+        text += &format!("#C {}\n", comment);
+    }
+
+    let mut leaves: HashMap<(usize, usize), [u8; LEAF_SIZE]> = HashMap::new();
+    for &(x, y) in &pattern.cells {
+        leaves.entry((x / LEAF_SIZE, y / LEAF_SIZE)).or_insert([0; LEAF_SIZE])[y % LEAF_SIZE] |= 1 << (x % LEAF_SIZE);
+    }
+    let mut ids: HashMap<Node, usize> = HashMap::new();
+    let mut lines = Vec::new();
+    let mut intern = |node: Node, lines: &mut Vec<String>| -> usize {
+        *ids.entry(node).or_insert_with(|| {
+            lines.push(match node {
+                Node::Leaf(rows) => leaf_line(&rows),
+                Node::Branch(level, [nw, ne, sw, se]) => format!("{} {} {} {} {}", level, nw, ne, sw, se),
+            });
+            lines.len()
+        })
+    };
+
+    let mut keys: Vec<_> = leaves.keys().copied().collect();
+    keys.sort_unstable_by_key(|&(x, y)| (y, x));
+    let mut blocks: HashMap<(usize, usize), usize> = keys.into_iter()
+        .map(|pos| (pos, intern(Node::Leaf(leaves[&pos]), &mut lines)))
+        .collect();
+    let mut level = LEAF_LEVEL;
+    let mut side = pattern.size.0.max(pattern.size.1).div_ceil(LEAF_SIZE).max(1);
+    while side > 1 || level == LEAF_LEVEL {
+        level += 1;
+        let mut parents: HashMap<(usize, usize), [usize; 4]> = HashMap::new();
+        for (&(bx, by), &id) in &blocks {
+            parents.entry((bx / 2, by / 2)).or_insert([0; 4])[(bx % 2) + (by % 2) * 2] = id;
+        }
+        let mut keys: Vec<_> = parents.keys().copied().collect();
+        keys.sort_unstable_by_key(|&(x, y)| (y, x));
+        blocks = keys.into_iter()
+            .map(|pos| (pos, intern(Node::Branch(level, parents[&pos]), &mut lines)))
+            .collect();
+        side = side.div_ceil(2);
+    }
+    for line in lines {
+        text += &line;
+        text.push('\n');
+    }
+    text
+}
+
+fn leaf_line(rows: &[u8; LEAF_SIZE]) -> String {
+    let height = rows.iter().rposition(|&row| row != 0).map_or(0, |y| y + 1);
+    let mut line = String::new();
+    for &row in &rows[..height] {
+        let width = LEAF_SIZE - row.leading_zeros() as usize;
+        line.extend((0..width).map(|x| if row & 1 << x != 0 { '*' } else { '.' }));
+        line.push('$');
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rle;
+
+    fn syntax_line(result: Result<Pattern, PatternError>) -> usize {
+        match result {
+            Err(PatternError::Syntax { line, .. }) => line,
+            other => panic!("expected a syntax error, got {:?}", other),
+        }
+    }
+
+    fn sorted(mut cells: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+        cells.sort_unstable_by_key(|&(x, y)| (y, x));
+        cells
+    }
+
+    #[test]
+    fn parses_a_golly_glider() {
+        let pattern = parse("[M2] (golly 2.0)\n#R B3/S23\n#N Glider\n.*$..*$***$\n4 1 0 0 0\n").unwrap();
+        assert_eq!(pattern.rule, Rule::parse("B3/S23"));
+        assert_eq!(pattern.name.as_deref(), Some("Glider"));
+        assert_eq!(pattern.size, (3, 3));
+        assert_eq!(sorted(pattern.cells), [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn round_trips_through_write() {
+        let mut pattern = rle::parse("x = 36, y = 9, rule = B36/S23\n\
+            24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b\n\
+            obo$10bo5bo7bo$11bo3bo$12b2o!").unwrap();
+        pattern.name = Some("Gun".to_string());
+        pattern.comments.push("generation 0".to_string());
+        let parsed = parse(&write(&pattern)).unwrap();
+        assert_eq!(parsed.name, pattern.name);
+        assert_eq!(parsed.comments, pattern.comments);
+        assert_eq!(parsed.rule, pattern.rule);
+        assert_eq!(parsed.size, pattern.size);
+        assert_eq!(sorted(parsed.cells), sorted(pattern.cells));
+    }
+
+    #[test]
+    fn shares_identical_subtrees() {
+        let pattern = Pattern {
+            size: (17, 1),
+            cells: vec![(0, 0), (8, 0), (16, 0)],
+            ..Default::default()
+        };
+        let text = write(&pattern);
+        assert_eq!(text.lines().filter(|line| line.starts_with('*')).count(), 1);
+        assert_eq!(sorted(parse(&text).unwrap().cells), pattern.cells);
+    }
+
+    #[test]
+    fn writes_an_empty_pattern() {
+        let parsed = parse(&write(&Pattern::default())).unwrap();
+        assert_eq!(parsed.size, (0, 0));
+        assert!(parsed.cells.is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert_eq!(syntax_line(parse("#R B3/S23\n*$\n")), 1);
+        assert_eq!(syntax_line(parse("[M2]\n#R B3/S99\n")), 2);
+        assert_eq!(syntax_line(parse("[M2]\n*x$\n")), 2);
+        assert_eq!(syntax_line(parse("[M2]\n*********$\n")), 2);
+        assert_eq!(syntax_line(parse("[M2]\n*$\n4 1 0 0\n")), 3);
+        assert_eq!(syntax_line(parse("[M2]\n*$\n3 1 0 0 0\n")), 3);
+        assert_eq!(syntax_line(parse("[M2]\n*$\n4 1 0 0 2\n")), 3);
+        assert_eq!(syntax_line(parse("[M2]\n*$\n4 1 0 0 0\n5 1 0 0 0\n")), 4);
+    }
+
+    #[test]
+    fn rejects_patterns_too_large_to_expand() {
+        // each level puts the last node in its top-left and bottom-right corners
+        let mut text = "[M2]\n*$\n".to_string();
+        for level in 4..=15 {
+            text += &format!("{} {} 0 0 {}\n", level, level - 3, level - 3);
+        }
+        assert!(matches!(parse(&text), Err(PatternError::TooLarge { .. })));
+    }
+
+    #[test]
+    fn rejects_dense_patterns_before_expanding_them() {
+        // a full 8x8 leaf repeated into every quadrant up to 8192x8192
+        let mut text = "[M2]\n".to_string() + &"********$".repeat(8) + "\n";
+        for level in 4..=13 {
+            text += &format!("{0} {1} {1} {1} {1}\n", level, level - 3);
+        }
+        assert_eq!(syntax_line(parse(&text)), 12);
+    }
+
+    #[test]
+    fn skips_blank_lines_before_the_header() {
+        let text = "\n[M2]\n*$\n4 1 0 0 0\n";
+        assert_eq!(Format::detect(text), Format::Macrocell);
+        assert_eq!(parse(text).unwrap().cells, [(0, 0)]);
+    }
+}
@@ -0,0 +1,150 @@
+use crate::life_cell::*;
+
+/// The neighbourhoods a Golly rule file can use.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Neighbourhood {
+    /// The four orthogonal neighbours.
+    VonNeumann,
+    /// All eight surrounding cells.
+    Moore,
+    /// Six neighbours, with a hexagonal grid drawn on a square one.
+    Hexagonal,
+    /// The left and right neighbours.
+    OneDimensional,
+}
+
+impl Neighbourhood {
+    /// Looks up a neighbourhood by its Golly name, ignoring case ("Moore", "vonNeumann", ...).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "vonneumann" => Some(Self::VonNeumann),
+            "moore" => Some(Self::Moore),
+            "hexagonal" => Some(Self::Hexagonal),
+            "onedimensional" => Some(Self::OneDimensional),
+            _ => None,
+        }
+    }
+
+    /// The cell itself followed by its neighbours clockwise from north, as in Golly's RuleTable.
+    pub fn offsets(self) -> &'static [(isize, isize)] {
+        match self {
+            Self::VonNeumann => &[(0, 0), (0, -1), (1, 0), (0, 1), (-1, 0)],
+            Self::Moore => &[(0, 0), (0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)],
+            Self::Hexagonal => &[(0, 0), (0, -1), (1, 0), (1, 1), (0, 1), (-1, 0), (-1, -1)],
+            Self::OneDimensional => &[(0, 0), (-1, 0), (1, 0)],
+        }
+    }
+
+    /// Number of neighbours, not counting the cell itself.
+    pub fn num_neighbours(self) -> usize {
+        self.offsets().len() - 1
+    }
+}
+
+/// A multi-state rule that a [`StateGrid`] can run.
+pub trait Transition {
+    /// Number of states, including the empty state 0.
+    fn num_states(&self) -> usize;
+    /// The neighbourhood the rule looks at.
+    fn neighbourhood(&self) -> Neighbourhood;
+    /// The next state of a cell, given its neighbourhood ordered as in [`Neighbourhood::offsets`].
+    fn next_state(&self, cells: &[u8]) -> u8;
+}
+
+/// How the edges of a [`StateGrid`] wrap.
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum Wrap {
+    /// Each row and each column wraps onto itself.
+    #[default]
+    Torus,
+    /// The cells form one ring, row after row, the way a
+    /// [`World`](crate::life_cell::World) wraps: stepping off the end of a row
+    /// lands on the start of the next one.
+    Ring,
+}
+
+/// A wrapped-edge grid of cells with up to 256 states, updated by a [`Transition`].
+pub struct StateGrid {
+    size: (usize, usize),
+    cells: Vec<u8>,
+    next_cells: Vec<u8>,
+    wrap: Wrap,
+}
+
+impl StateGrid {
+    /// Creates a grid of `size` cells, all in state 0.
+    pub fn new(size: (usize, usize)) -> Self {
+        Self {
+            size,
+            cells: vec![0; size.0 * size.1],
+            next_cells: vec![0; size.0 * size.1],
+            wrap: Wrap::default(),
+        }
+    }
+
+    /// Changes how the edges wrap.
+    pub fn set_wrap(&mut self, wrap: Wrap) {
+        self.wrap = wrap;
+    }
+
+    /// The grid size in cells (width, height).
+    pub fn size(&self) -> (usize, usize) {
+        self.size
+    }
+
+    /// The state of the cell at `pos`; coordinates wrap around the edges.
+    pub fn read_cell(&self, pos: (usize, usize)) -> u8 {
+        self.cells[(pos.0 % self.size.0) + (pos.1 % self.size.1) * self.size.0]
+    }
+
+    /// Sets the state of the cell at `pos`.
+    pub fn set_cell(&mut self, pos: (usize, usize), state: u8) {
+        self.cells[(pos.0 % self.size.0) + (pos.1 % self.size.1) * self.size.0] = state;
+    }
+
+    /// Brings the grid in line with the live cells of `world`, e.g. after it
+    /// was edited: live cells in state 0 here become state 1, dead ones become
+    /// state 0 and the rest keep their states. `world` must be the same size.
+    pub fn follow(&mut self, world: &World) {
+        for (linear_pos, state) in self.cells.iter_mut().enumerate() {
+            let live = world.read_cell((linear_pos % self.size.0, linear_pos / self.size.0)).state == CellState::Live;
+            if !live {
+                *state = 0;
+            } else if *state == 0 {
+                *state = 1;
+            }
+        }
+    }
+
+    /// Number of cells not in state 0.
+    pub fn population(&self) -> usize {
+        self.cells.iter().filter(|&&state| state != 0).count()
+    }
+
+    /// Advances the grid by one generation of `rule` and returns how many cells changed.
+    pub fn update<T: Transition>(&mut self, rule: &T) -> usize {
+        let offsets = rule.neighbourhood().offsets();
+        let (width, height) = (self.size.0 as isize, self.size.1 as isize);
+        let mut neighbourhood = [0u8; 9];
+        let mut changes = 0;
+        for y in 0..height {
+            for x in 0..width {
+                for (idx, &(dx, dy)) in offsets.iter().enumerate() {
+                    let neighbour_pos = match self.wrap {
+                        Wrap::Torus => (x + dx).rem_euclid(width) + (y + dy).rem_euclid(height) * width,
+                        Wrap::Ring => (x + dx + (y + dy) * width).rem_euclid(width * height),
+                    };
+                    neighbourhood[idx] = self.cells[neighbour_pos as usize];
+                }
+                let linear_pos = (x + y * width) as usize;
+                let state = rule.next_state(&neighbourhood[..offsets.len()]);
+                if state != self.cells[linear_pos] {
+                    changes += 1;
+                }
+                self.next_cells[linear_pos] = state;
+            }
+        }
+        std::mem::swap(&mut self.cells, &mut self.next_cells);
+        changes
+    }
+}
@@ -0,0 +1,278 @@
+use std::{
+    fmt,
+    path::Path,
+};
+use crate::{
+    life_cell::*,
+    rule::*,
+    rle,
+    plaintext,
+    life105,
+    life106,
+    macrocell,
+};
+
+/// A pattern file format.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Format {
+    /// Run-length encoded (`.rle`).
+    Rle,
+    /// Plaintext `.`/`O` grid (`.cells`).
+    Plaintext,
+    /// Life 1.05 (`.lif`).
+    Life105,
+    /// Life 1.06 coordinate list (`.lif`, `.life`).
+    Life106,
+    /// Golly macrocell quadtree (`.mc`).
+    Macrocell,
+}
+
+impl Format {
+    /// Guesses the format of `text` from its header line, falling back to RLE.
+    pub fn detect(text: &str) -> Self {
+        let mut lines = text.lines().map(|line| line.trim()).filter(|line| !line.is_empty());
+        let Some(first_line) = lines.next() else {
+            return Self::Plaintext;
+        };
+        if first_line.starts_with(life105::HEADER) {
+            return Self::Life105;
+        }
+        if first_line.starts_with(life106::HEADER) {
+            return Self::Life106;
+        }
+        if first_line.starts_with(macrocell::HEADER) {
+            return Self::Macrocell;
+        }
+        if first_line.starts_with('!') {
+            return Self::Plaintext;
+        }
+        match std::iter::once(first_line).chain(lines).find(|line| !line.starts_with('#')) {
+            Some(line) if line.chars().all(|c| matches!(c, '.' | 'O' | '*')) => Self::Plaintext,
+            _ => Self::Rle,
+        }
+    }
+
+    /// The format written for `path`, from its extension (`.lif` means Life 1.06).
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "rle" => Some(Self::Rle),
+            "cells" => Some(Self::Plaintext),
+            "lif" | "life" => Some(Self::Life106),
+            "mc" => Some(Self::Macrocell),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a pattern in any supported format, detected from its contents.
+pub fn parse(text: &str) -> Result<Pattern, PatternError> {
+    match Format::detect(text) {
+        Format::Rle => rle::parse(text),
+        Format::Plaintext => plaintext::parse(text),
+        Format::Life105 => life105::parse(text),
+        Format::Life106 => life106::parse(text),
+        Format::Macrocell => macrocell::parse(text),
+    }
+}
+
+/// Reads and parses a pattern file.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Pattern, PatternError> {
+    parse(&std::fs::read_to_string(path)?)
+}
+
+/// Formats `pattern` as `format`.
+pub fn write(pattern: &Pattern, format: Format) -> String {
+    match format {
+        Format::Rle => rle::write(pattern),
+        Format::Plaintext => plaintext::write(pattern),
+        Format::Life105 => life105::write(pattern),
+        Format::Life106 => life106::write(pattern),
+        Format::Macrocell => macrocell::write(pattern),
+    }
+}
+
+/// Writes `pattern` to `path` in the format given by its extension, RLE if unknown.
+pub fn save<P: AsRef<Path>>(pattern: &Pattern, path: P) -> Result<(), PatternError> {
+    let format = Format::from_path(&path).unwrap_or(Format::Rle);
+    std::fs::write(path, write(pattern, format))?;
+    Ok(())
+}
+
+/// The widest and tallest pattern the coordinate formats can describe.
+pub const MAX_PATTERN_SIZE: usize = isize::MAX as usize;
+
+/// An error reading or placing a pattern.
+#[derive(Debug)]
+pub enum PatternError {
+    /// The file couldn't be read or written.
+    Io(std::io::Error),
+    /// The text isn't valid in its format.
+    Syntax {
+        /// 1-based line number.
+        line: usize,
+        /// What was wrong.
+        message: String,
+    },
+    /// The pattern doesn't fit in the world.
+    TooLarge {
+        /// Width and height of the pattern.
+        size: (usize, usize),
+        /// Width and height of the space available.
+        limit: (usize, usize),
+    },
+}
+
+impl PatternError {
+    pub(crate) fn syntax<S: Into<String>>(line: usize, message: S) -> Self {
+        Self::Syntax { line, message: message.into() }
+    }
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            Self::TooLarge { size, limit } => write!(
+                f, "pattern is {}x{} but only {}x{} fits", size.0, size.1, limit.0, limit.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+impl From<std::io::Error> for PatternError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// A finite pattern with its metadata, shared by every file format.
+#[derive(Default, Debug, Clone)]
+pub struct Pattern {
+    /// The pattern's name, if the file gives one.
+    pub name: Option<String>,
+    /// The pattern's author, if the file gives one.
+    pub author: Option<String>,
+    /// Any other comment lines.
+    pub comments: Vec<String>,
+    /// The rule the pattern was made for, if the file says.
+    pub rule: Option<Rule>,
+    /// Width and height of the pattern.
+    pub size: (usize, usize),
+    /// Positions of the live cells, relative to the top-left corner.
+    pub cells: Vec<(usize, usize)>,
+}
+
+impl Pattern {
+    /// Copies the live cells in `region` (position, size) of `world`, or of the
+    /// whole world if `None`, cropped to their bounding box.
+    pub fn from_world(world: &World, region: Option<((usize, usize), (usize, usize))>) -> Self {
+        let (pos, size) = region.unwrap_or(((0, 0), world.size()));
+        let mut cells = Vec::new();
+        for y in 0..size.1 {
+            for x in 0..size.0 {
+                if world.read_cell((pos.0 + x, pos.1 + y)).state == CellState::Live {
+                    cells.push((x, y));
+                }
+            }
+        }
+        let mut pattern = Self {
+            rule: Some(world.rule()),
+            cells,
+            ..Default::default()
+        };
+        pattern.crop_to_bounding_box();
+        pattern
+    }
+
+    /// Replaces the cells with `cells`, shifted so the topmost and leftmost are at 0.
+    ///
+    /// Fails with [`PatternError::TooLarge`] if the cells span more than
+    /// [`MAX_PATTERN_SIZE`] in either direction.
+    pub fn set_signed_cells(&mut self, cells: &[(isize, isize)]) -> Result<(), PatternError> {
+        let x0 = cells.iter().map(|&(x, _)| x).min().unwrap_or(0);
+        let y0 = cells.iter().map(|&(_, y)| y).min().unwrap_or(0);
+        let x1 = cells.iter().map(|&(x, _)| x).max().unwrap_or(0);
+        let y1 = cells.iter().map(|&(_, y)| y).max().unwrap_or(0);
+        let size = (x1.abs_diff(x0).saturating_add(1), y1.abs_diff(y0).saturating_add(1));
+        if size.0 > MAX_PATTERN_SIZE || size.1 > MAX_PATTERN_SIZE {
+            return Err(PatternError::TooLarge { size, limit: (MAX_PATTERN_SIZE, MAX_PATTERN_SIZE) });
+        }
+        self.cells = cells.iter().map(|&(x, y)| (x.abs_diff(x0), y.abs_diff(y0))).collect();
+        self.crop_to_bounding_box();
+        Ok(())
+    }
+
+    /// The pattern as rows of cells, `true` for alive.
+    pub fn rows(&self) -> Vec<Vec<bool>> {
+        let mut rows = vec![vec![false; self.size.0]; self.size.1];
+        for &(x, y) in &self.cells {
+            rows[y][x] = true;
+        }
+        rows
+    }
+
+    /// Shifts the cells to the top-left corner and shrinks `size` to fit them.
+    pub fn crop_to_bounding_box(&mut self) {
+        let (Some(x0), Some(y0)) = (
+            self.cells.iter().map(|&(x, _)| x).min(),
+            self.cells.iter().map(|&(_, y)| y).min(),
+        ) else {
+            self.size = (0, 0);
+            return;
+        };
+        let x1 = self.cells.iter().map(|&(x, _)| x).max().unwrap();
+        let y1 = self.cells.iter().map(|&(_, y)| y).max().unwrap();
+        for cell in &mut self.cells {
+            *cell = (cell.0 - x0, cell.1 - y0);
+        }
+        self.size = (x1 - x0 + 1, y1 - y0 + 1);
+    }
+
+    /// Rotates the pattern a quarter turn clockwise.
+    pub fn rotate_clockwise(&mut self) {
+        let height = self.size.1;
+        for cell in &mut self.cells {
+            *cell = (height - 1 - cell.1, cell.0);
+        }
+        self.size = (self.size.1, self.size.0);
+    }
+
+    /// Mirrors the pattern left to right.
+    pub fn flip_horizontal(&mut self) {
+        let width = self.size.0;
+        for cell in &mut self.cells {
+            cell.0 = width - 1 - cell.0;
+        }
+    }
+
+    /// Mirrors the pattern top to bottom.
+    pub fn flip_vertical(&mut self) {
+        let height = self.size.1;
+        for cell in &mut self.cells {
+            cell.1 = height - 1 - cell.1;
+        }
+    }
+
+    /// Fails with [`PatternError::TooLarge`] if the pattern is bigger than `size`.
+    pub fn check_fits(&self, size: (usize, usize)) -> Result<(), PatternError> {
+        if self.size.0 > size.0 || self.size.1 > size.1 {
+            Err(PatternError::TooLarge { size: self.size, limit: size })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sets the pattern's cells alive in `world` with its top-left corner at `pos`
+    /// and returns the change in population. The pattern's rule is not applied.
+    pub fn place(&self, world: &mut World, pos: (usize, usize)) -> i32 {
+        let mut growth = 0;
+        for &(x, y) in &self.cells {
+            growth += world.set_cell((pos.0 + x, pos.1 + y), CellState::Live);
+        }
+        growth
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{
+    pattern::*,
+    rle,
+};
+
+/// A well-known pattern built into the crate.
+pub struct LibraryEntry {
+    /// Short name used to look the pattern up, e.g. "gosper-gun".
+    pub id: &'static str,
+    /// Full name of the pattern.
+    pub name: &'static str,
+    rle: &'static str,
+}
+
+impl LibraryEntry {
+    /// Parses the entry into a [`Pattern`].
+    pub fn pattern(&self) -> Pattern {
+        let mut pattern = rle::parse(self.rle).unwrap();
+        pattern.name = Some(self.name.to_string());
+        pattern
+    }
+}
+
+/// Looks up a library entry by id, ignoring case.
+pub fn find(id: &str) -> Option<&'static LibraryEntry> {
+    LIBRARY.iter().find(|entry| entry.id.eq_ignore_ascii_case(id))
+}
+
+/// Every built-in pattern, in the order the library browser lists them.
+///
+/// Only patterns whose behaviour has been checked by running them are
+/// listed, so there is no breeder yet.
+pub const LIBRARY: &[LibraryEntry] = &[
+    LibraryEntry {
+        id: "glider",
+        name: "Glider",
+        rle: "x = 3, y = 3, rule = B3/S23\nbob$2bo$3o!",
+    },
+    LibraryEntry {
+        id: "lwss",
+        name: "Lightweight spaceship",
+        rle: "x = 5, y = 4, rule = B3/S23\nbo2bo$o4b$o3bo$4o!",
+    },
+    LibraryEntry {
+        id: "mwss",
+        name: "Middleweight spaceship",
+        rle: "x = 6, y = 5, rule = B3/S23\n3bo2b$bo3bo$o5b$o4bo$5o!",
+    },
+    LibraryEntry {
+        id: "hwss",
+        name: "Heavyweight spaceship",
+        rle: "x = 7, y = 5, rule = B3/S23\n3b2o2b$bo4bo$o6b$o5bo$6o!",
+    },
+    LibraryEntry {
+        id: "gosper-gun",
+        name: "Gosper glider gun",
+        rle: "x = 36, y = 9, rule = B3/S23\n\
+            24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b\n\
+            obo$10bo5bo7bo$11bo3bo$12b2o!",
+    },
+    LibraryEntry {
+        id: "r-pentomino",
+        name: "R-pentomino",
+        rle: "x = 3, y = 3, rule = B3/S23\nb2o$2o$bo!",
+    },
+    LibraryEntry {
+        id: "acorn",
+        name: "Acorn",
+        rle: "x = 7, y = 3, rule = B3/S23\nbo$3bo$2o2b3o!",
+    },
+    LibraryEntry {
+        id: "diehard",
+        name: "Diehard",
+        rle: "x = 8, y = 3, rule = B3/S23\n6bob$2o6b$bo3b3o!",
+    },
+    LibraryEntry {
+        id: "pulsar",
+        name: "Pulsar",
+        rle: "x = 13, y = 13, rule = B3/S23\n\
+            2b3o3b3o2b2$o4bobo4bo$o4bobo4bo$o4bobo4bo$2b3o3b3o2b2$2b3o3b3o2b$o4bob\n\
+            o4bo$o4bobo4bo$o4bobo4bo2$2b3o3b3o!",
+    },
+    LibraryEntry {
+        id: "pentadecathlon",
+        name: "Pentadecathlon",
+        rle: "x = 10, y = 3, rule = B3/S23\n2bo4bo2b$2ob4ob2o$2bo4bo!",
+    },
+    LibraryEntry {
+        id: "switch-engine",
+        name: "Switch engine",
+        rle: "x = 6, y = 4, rule = B3/S23\nbobo2b$o5b$bo2bob$3b3o!",
+    },
+    LibraryEntry {
+        id: "blse",
+        name: "Block-laying switch engine",
+        rle: "x = 8, y = 6, rule = B3/S23\n6bob$4bob2o$4bobo$4bo$2bo$obo!",
+    },
+    LibraryEntry {
+        id: "puffer-train",
+        name: "Puffer train",
+        rle: "x = 5, y = 18, rule = B3/S23\n3bo$4bo$o3bo$b4o4$o$b2o$2bo$2bo$bo3$3bo$4bo$o3bo$b4o!",
+    },
+    LibraryEntry {
+        id: "blinker-puffer",
+        name: "Blinker puffer 1",
+        rle: "x = 9, y = 18, rule = B3/S23\n\
+            3bo$bo3bo$o$o4bo$5o4$b2o$2ob3o$b4o$2b2o2$5b2o$3bo4bo$2bo$2bo5bo$2b6o!",
+    },
+];
@@ -0,0 +1,92 @@
+use crate::pattern::*;
+
+pub fn parse(text: &str) -> Result<Pattern, PatternError> {
+    let mut pattern = Pattern::default();
+    let mut rows = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = line.trim_end();
+        if let Some(comment) = line.strip_prefix('!') {
+            if let Some(name) = comment.strip_prefix("Name:") {
+                pattern.name = Some(name.trim().to_string());
+            } else if let Some(author) = comment.strip_prefix("Author:") {
+                pattern.author = Some(author.trim().to_string());
+            } else {
+                pattern.comments.push(comment.trim().to_string());
+            }
+            continue;
+        }
+        let mut row = Vec::new();
+        for (column, c) in line.chars().enumerate() {
+            match c {
+                '.' => row.push(false),
+                'O' | '*' => row.push(true),
+                c => return Err(PatternError::syntax(line_no, format!("unexpected character '{}' at column {}", c, column + 1))),
+            }
+        }
+        rows.push(row);
+    }
+    while rows.last().is_some_and(|row| row.is_empty()) {
+        rows.pop();
+    }
+    pattern.size = (rows.iter().map(|row| row.len()).max().unwrap_or(0), rows.len());
+    for (y, row) in rows.iter().enumerate() {
+        for (x, &live) in row.iter().enumerate() {
+            if live {
+                pattern.cells.push((x, y));
+            }
+        }
+    }
+    Ok(pattern)
+}
+
+pub fn write(pattern: &Pattern) -> String {
+    let mut text = String::new();
+    if let Some(name) = &pattern.name {
+        text += &format!("!Name: {}\n", name);
+    }
+    if let Some(author) = &pattern.author {
+        text += &format!("!Author: {}\n", author);
+    }
+    for comment in &pattern.comments {
+        text += &format!("!{}\n", comment);
+    }
+    for row in pattern.rows() {
+        let width = row.iter().rposition(|&live| live).map_or(0, |x| x + 1);
+        text.extend(row[..width].iter().map(|&live| if live { 'O' } else { '.' }));
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_comments_and_rows() {
+        let pattern = parse("!Name: Glider\n!Author: Richard Guy\n!a comment\n.O\n..*\nOOO\n\n").unwrap();
+        assert_eq!(pattern.name.as_deref(), Some("Glider"));
+        assert_eq!(pattern.author.as_deref(), Some("Richard Guy"));
+        assert_eq!(pattern.comments, ["a comment"]);
+        assert_eq!(pattern.size, (3, 3));
+        assert_eq!(pattern.cells, [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn round_trips_through_write() {
+        let pattern = parse("!Name: Blinker and block\nOOO\n\n....OO\n....OO\n").unwrap();
+        let parsed = parse(&write(&pattern)).unwrap();
+        assert_eq!(parsed.name, pattern.name);
+        assert_eq!(parsed.size, pattern.size);
+        assert_eq!(parsed.cells, pattern.cells);
+    }
+
+    #[test]
+    fn rejects_unknown_characters() {
+        match parse(".O\n.X\n") {
+            Err(PatternError::Syntax { line: 2, message }) => assert!(message.contains("column 2")),
+            other => panic!("expected a syntax error on line 2, got {:?}", other),
+        }
+    }
+}
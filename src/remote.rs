@@ -0,0 +1,281 @@
+use std::io::{
+    self,
+    ErrorKind,
+    Read,
+    Write,
+};
+use std::net::{
+    SocketAddr,
+    TcpListener,
+    TcpStream,
+};
+#[cfg(unix)]
+use std::os::unix::net::{
+    UnixListener,
+    UnixStream,
+};
+use crate::command::*;
+use crate::json::*;
+
+const DEFAULT_HOST: &str = "127.0.0.1";
+// a client sending a longer line without a newline is cut off
+const MAX_LINE_LENGTH: usize = 1 << 20;
+// a client that lets more replies than this pile up unread is dropped
+const MAX_OUTPUT_LENGTH: usize = 1 << 22;
+
+/// Where the remote control listens.
+#[derive(PartialEq, Debug, Clone)]
+pub enum ListenAddress {
+    /// A TCP port on a loopback address.
+    Tcp(SocketAddr),
+    /// A Unix-domain socket at the given path.
+    #[cfg(unix)]
+    Unix(String),
+}
+
+impl ListenAddress {
+    /// Parses `PORT`, `HOST:PORT` or `unix:PATH`. Only loopback hosts are accepted.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        if let Some(path) = text.strip_prefix("unix:") {
+            #[cfg(unix)]
+            return match path {
+                "" => Err("unix: needs a socket path".to_string()),
+                path => Ok(Self::Unix(path.to_string())),
+            };
+            #[cfg(not(unix))]
+            return Err(format!("Unix-domain sockets are not available here (\"{}\")", path));
+        }
+        let text = if text.contains(':') { text.to_string() } else { format!("{}:{}", DEFAULT_HOST, text) };
+        let addr = text.replace("localhost:", &format!("{}:", DEFAULT_HOST)).parse::<SocketAddr>()
+            .map_err(|_| format!("expected PORT, HOST:PORT or unix:PATH, not \"{}\"", text))?;
+        if !addr.ip().is_loopback() {
+            return Err(format!("{} is not a loopback address", addr.ip()));
+        }
+        Ok(Self::Tcp(addr))
+    }
+}
+
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, String),
+}
+
+enum Stream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Tcp(stream) => stream.read(buf),
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Tcp(stream) => stream.write(buf),
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Tcp(stream) => stream.flush(),
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+struct Client {
+    id: u64,
+    stream: Stream,
+    input: Vec<u8>,
+    output: Vec<u8>,
+    closed: bool,
+}
+
+impl Client {
+    // reads whatever has arrived and returns the complete lines
+    fn read_lines(&mut self) -> Vec<String> {
+        let mut buf = [0; 4096];
+        loop {
+            match self.stream.read(&mut buf) {
+                Ok(0) => {
+                    self.closed = true;
+                    break;
+                }
+                Ok(len) => self.input.extend_from_slice(&buf[..len]),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => {
+                    self.closed = true;
+                    break;
+                }
+            }
+        }
+        let mut lines = Vec::new();
+        while let Some(end) = self.input.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.input.drain(..=end).collect();
+            lines.push(String::from_utf8_lossy(&line).trim().to_string());
+        }
+        if self.input.len() > MAX_LINE_LENGTH {
+            self.closed = true;
+        }
+        lines.retain(|line| !line.is_empty());
+        lines
+    }
+
+    // sends as much of the pending output as the socket takes without blocking
+    fn flush(&mut self) {
+        while !self.output.is_empty() {
+            match self.stream.write(&self.output) {
+                Ok(0) => {
+                    self.closed = true;
+                    return;
+                }
+                Ok(len) => {
+                    self.output.drain(..len);
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return,
+                Err(_) => {
+                    self.closed = true;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Who sent a command, for replying to it.
+pub struct Request {
+    /// The client connection.
+    pub client: u64,
+    /// The request's `"id"` member, echoed back in the response so clients
+    /// can match them up.
+    pub id: Option<Json>,
+}
+
+/// A listening socket and its clients, each sending one JSON request per
+/// line. Everything is non-blocking, so it can be polled once a frame.
+pub struct Remote {
+    listener: Listener,
+    clients: Vec<Client>,
+    next_id: u64,
+}
+
+impl Remote {
+    /// Starts listening at `address`.
+    pub fn bind(address: &ListenAddress) -> io::Result<Self> {
+        let listener = match address {
+            ListenAddress::Tcp(addr) => {
+                let listener = TcpListener::bind(addr)?;
+                listener.set_nonblocking(true)?;
+                Listener::Tcp(listener)
+            }
+            #[cfg(unix)]
+            ListenAddress::Unix(path) => {
+                // a socket left behind by a previous run that nobody answers on
+                if UnixStream::connect(path).is_err() && is_socket(path) {
+                    std::fs::remove_file(path)?;
+                }
+                let listener = UnixListener::bind(path)?;
+                listener.set_nonblocking(true)?;
+                Listener::Unix(listener, path.clone())
+            }
+        };
+        Ok(Self { listener, clients: Vec::new(), next_id: 0 })
+    }
+
+    /// Accepts new clients and returns the requests received since the last call.
+    pub fn poll(&mut self) -> Vec<(Request, Result<Command, String>)> {
+        // dropped one frame late, so a client that shut down its end after
+        // sending still gets the replies to its last requests
+        self.clients.retain(|client| !client.closed);
+        loop {
+            let stream = match &self.listener {
+                Listener::Tcp(listener) => listener.accept().and_then(|(stream, _)| {
+                    stream.set_nonblocking(true)?;
+                    stream.set_nodelay(true)?;
+                    Ok(Stream::Tcp(stream))
+                }),
+                #[cfg(unix)]
+                Listener::Unix(listener, _) => listener.accept().and_then(|(stream, _)| {
+                    stream.set_nonblocking(true)?;
+                    Ok(Stream::Unix(stream))
+                }),
+            };
+            match stream {
+                Ok(stream) => {
+                    self.clients.push(Client { id: self.next_id, stream, input: Vec::new(), output: Vec::new(), closed: false });
+                    self.next_id += 1;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => {
+                    eprintln!("remote: {}", e);
+                    break;
+                }
+            }
+        }
+        let mut requests = Vec::new();
+        for client in &mut self.clients {
+            for line in client.read_lines() {
+                match Json::parse(&line) {
+                    Ok(request) => requests.push((
+                        Request { client: client.id, id: request.get("id").cloned() },
+                        Command::from_json(&request),
+                    )),
+                    // not a remote control client, e.g. a web page posting to
+                    // the port, so nothing more it sends is run
+                    Err(e) => {
+                        requests.push((Request { client: client.id, id: None }, Err(format!("invalid JSON: {}", e))));
+                        client.closed = true;
+                        break;
+                    }
+                }
+            }
+            client.flush();
+        }
+        requests
+    }
+
+    /// Sends the response to `request`, as one JSON object holding the
+    /// `response` members.
+    pub fn reply(&mut self, request: &Request, mut response: Vec<(String, Json)>) {
+        if let Some(client) = self.clients.iter_mut().find(|client| client.id == request.client) {
+            if let Some(id) = &request.id {
+                response.insert(0, ("id".to_string(), id.clone()));
+            }
+            client.output.extend(format!("{}\n", Json::Object(response)).bytes());
+            client.flush();
+            if client.output.len() > MAX_OUTPUT_LENGTH {
+                client.closed = true;
+            }
+        }
+    }
+}
+
+impl Drop for Remote {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Listener::Unix(_, path) = &self.listener {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_socket(path: &str) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket())
+}
@@ -0,0 +1,233 @@
+use crate::{
+    pattern::*,
+    rule::*,
+};
+
+pub fn parse(text: &str) -> Result<Pattern, PatternError> {
+    let mut pattern = Pattern::default();
+    let mut lines = text.lines().enumerate().map(|(idx, line)| (idx + 1, line.trim()));
+
+    let header_line_no = loop {
+        let Some((line_no, line)) = lines.next() else {
+            return Err(PatternError::syntax(text.lines().count().max(1), "missing \"x = ..., y = ...\" header"));
+        };
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix('#') {
+            let (tag, body) = comment.split_at(comment.chars().next().map_or(0, |c| c.len_utf8()));
+            let body = body.trim().to_string();
+            match tag {
+                "N" => pattern.name = Some(body),
+                "O" => pattern.author = Some(body),
+                _ => pattern.comments.push(body),
+            }
+            continue;
+        }
+        parse_header(line_no, line, &mut pattern)?;
+        break line_no;
+    };
+
+    let (mut x, mut y): (usize, usize) = (0, 0);
+    let mut run: Option<usize> = None;
+    let mut last_line_no = header_line_no;
+    for (line_no, line) in lines {
+        last_line_no = line_no;
+        for (column, c) in line.chars().enumerate() {
+            match c {
+                '0'..='9' => {
+                    let digit = c.to_digit(10).unwrap() as usize;
+                    let n = run.unwrap_or(0).checked_mul(10).and_then(|n| n.checked_add(digit));
+                    run = Some(n.ok_or_else(|| PatternError::syntax(line_no, format!("run count too large at column {}", column + 1)))?);
+                }
+                'b' | '.' => {
+                    x = x.checked_add(run.take().unwrap_or(1))
+                        .ok_or_else(|| PatternError::syntax(line_no, format!("run too long at column {}", column + 1)))?;
+                }
+                'o' | 'A' => {
+                    for _ in 0..run.take().unwrap_or(1) {
+                        if x >= pattern.size.0 || y >= pattern.size.1 {
+                            return Err(PatternError::syntax(line_no, format!(
+                                "cell ({}, {}) outside the {}x{} pattern area", x, y, pattern.size.0, pattern.size.1
+                            )));
+                        }
+                        pattern.cells.push((x, y));
+                        x += 1;
+                    }
+                }
+                '$' => {
+                    y = y.checked_add(run.take().unwrap_or(1))
+                        .ok_or_else(|| PatternError::syntax(line_no, format!("run too long at column {}", column + 1)))?;
+                    x = 0;
+                }
+                '!' => {
+                    if run.is_some() {
+                        return Err(PatternError::syntax(line_no, "run count without a tag before '!'"));
+                    }
+                    return Ok(pattern);
+                }
+                c if c.is_whitespace() => {}
+                c => return Err(PatternError::syntax(line_no, format!("unexpected character '{}' at column {}", c, column + 1))),
+            }
+        }
+    }
+    Err(PatternError::syntax(last_line_no, "missing '!' at the end of the pattern"))
+}
+
+fn parse_header(line_no: usize, line: &str, pattern: &mut Pattern) -> Result<(), PatternError> {
+    let (mut width, mut height) = (None, None);
+    for item in line.split(',') {
+        let Some((key, value)) = item.split_once('=') else {
+            return Err(PatternError::syntax(line_no, format!("malformed header item \"{}\"", item.trim())));
+        };
+        let (key, value) = (key.trim(), value.trim());
+        match key {
+            "x" | "y" => {
+                let n = value.parse::<usize>()
+                    .map_err(|_| PatternError::syntax(line_no, format!("invalid {} value \"{}\"", key, value)))?;
+                if key == "x" { width = Some(n) } else { height = Some(n) }
+            }
+            "rule" => {
+                pattern.rule = Some(Rule::parse(value)
+                    .ok_or_else(|| PatternError::syntax(line_no, format!("unsupported rule \"{}\"", value)))?);
+            }
+            _ => return Err(PatternError::syntax(line_no, format!("unknown header key \"{}\"", key))),
+        }
+    }
+    match (width, height) {
+        (Some(width), Some(height)) => {
+            pattern.size = (width, height);
+            Ok(())
+        }
+        _ => Err(PatternError::syntax(line_no, "header must give both x and y")),
+    }
+}
+
+const LINE_LENGTH: usize = 70;
+
+pub fn write(pattern: &Pattern) -> String {
+    let mut text = String::new();
+    if let Some(name) = &pattern.name {
+        text += &format!("#N {}\n", name);
+    }
+    if let Some(author) = &pattern.author {
+        text += &format!("#O {}\n", author);
+    }
+    for comment in &pattern.comments {
+        text += &format!("#C {}\n", comment);
+    }
+    text += &format!("x = {}, y = {}, rule = {}\n", pattern.size.0, pattern.size.1, pattern.rule.unwrap_or_default());
+
+    let mut cells = pattern.cells.clone();
+    cells.sort_by_key(|&(x, y)| (y, x));
+    cells.dedup();
+    let mut tokens = Vec::new();
+    let push_run = |tokens: &mut Vec<String>, n: usize, tag: char| {
+        match n {
+            0 => {}
+            1 => tokens.push(tag.to_string()),
+            _ => tokens.push(format!("{}{}", n, tag)),
+        }
+    };
+    let (mut x, mut y) = (0, 0);
+    let mut idx = 0;
+    while idx < cells.len() {
+        let (cx, cy) = cells[idx];
+        if cy > y {
+            push_run(&mut tokens, cy - y, '$');
+            (x, y) = (0, cy);
+        }
+        let mut end = idx + 1;
+        while end < cells.len() && cells[end] == (cx + end - idx, cy) {
+            end += 1;
+        }
+        push_run(&mut tokens, cx - x, 'b');
+        push_run(&mut tokens, end - idx, 'o');
+        x = cx + end - idx;
+        idx = end;
+    }
+    tokens.push("!".to_string());
+
+    let mut line_length = 0;
+    for token in tokens {
+        if line_length + token.len() > LINE_LENGTH {
+            text.push('\n');
+            line_length = 0;
+        }
+        line_length += token.len();
+        text += &token;
+    }
+    text.push('\n');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn syntax_line(result: Result<Pattern, PatternError>) -> usize {
+        match result {
+            Err(PatternError::Syntax { line, .. }) => line,
+            other => panic!("expected a syntax error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_header_comments_and_runs() {
+        let pattern = parse("#N Glider\n#O Richard Guy\n#C a comment\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n").unwrap();
+        assert_eq!(pattern.name.as_deref(), Some("Glider"));
+        assert_eq!(pattern.author.as_deref(), Some("Richard Guy"));
+        assert_eq!(pattern.comments, ["a comment"]);
+        assert_eq!(pattern.rule, Rule::parse("B3/S23"));
+        assert_eq!(pattern.size, (3, 3));
+        assert_eq!(pattern.cells, [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn runs_may_span_lines_and_skip_rows() {
+        let pattern = parse("x = 4, y = 4\n2o\n2b$\n2$3bo!").unwrap();
+        assert_eq!(pattern.rule, None);
+        assert_eq!(pattern.cells, [(0, 0), (1, 0), (3, 3)]);
+    }
+
+    #[test]
+    fn round_trips_through_write() {
+        let mut pattern = parse("x = 36, y = 9, rule = B36/S23\n\
+            24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b\n\
+            obo$10bo5bo7bo$11bo3bo$12b2o!").unwrap();
+        pattern.name = Some("Gun".to_string());
+        pattern.comments.push("generation 0".to_string());
+        let text = write(&pattern);
+        assert!(text.lines().all(|line| line.len() <= LINE_LENGTH));
+        let parsed = parse(&text).unwrap();
+        assert_eq!(parsed.name, pattern.name);
+        assert_eq!(parsed.comments, pattern.comments);
+        assert_eq!(parsed.rule, pattern.rule);
+        assert_eq!(parsed.size, pattern.size);
+        assert_eq!(parsed.cells, pattern.cells);
+    }
+
+    #[test]
+    fn writes_an_empty_pattern() {
+        let parsed = parse(&write(&Pattern::default())).unwrap();
+        assert_eq!(parsed.size, (0, 0));
+        assert!(parsed.cells.is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert_eq!(syntax_line(parse("")), 1);
+        assert_eq!(syntax_line(parse("#C only a comment\n")), 1);
+        assert_eq!(syntax_line(parse("x = 3\n3o!")), 1);
+        assert_eq!(syntax_line(parse("x = 3, y = a\n3o!")), 1);
+        assert_eq!(syntax_line(parse("x = 3, y = 1, z = 2\n3o!")), 1);
+        assert_eq!(syntax_line(parse("x = 3, y = 1, rule = B9/S23\n3o!")), 1);
+        assert_eq!(syntax_line(parse("x = 3, y = 1\n4o!")), 2);
+        assert_eq!(syntax_line(parse("x = 3, y = 1\n\n3q!")), 3);
+        assert_eq!(syntax_line(parse("x = 3, y = 1\n3o2!")), 2);
+        assert_eq!(syntax_line(parse("x = 3, y = 1\n3o\n")), 2);
+        assert_eq!(syntax_line(parse("x = 3, y = 1\n99999999999999999999999o!")), 2);
+        assert_eq!(syntax_line(parse("x = 3, y = 1\n18446744073709551615$18446744073709551615$o!")), 2);
+        assert_eq!(syntax_line(parse("x = 3, y = 1\n18446744073709551615b18446744073709551615bo!")), 2);
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt;
+use crate::multi_state::*;
+
+/// A two-state outer-totalistic rule in B/S notation, e.g. B3/S23 for Conway's Life.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Rule {
+    birth: u16,
+    survival: u16,
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self { birth: 1 << 3, survival: 1 << 2 | 1 << 3 }
+    }
+}
+
+impl Rule {
+    /// Whether a dead cell with `neighbours` live neighbours comes alive.
+    pub fn birth(&self, neighbours: usize) -> bool {
+        self.birth & 1 << neighbours != 0
+    }
+
+    /// Whether a live cell with `neighbours` live neighbours stays alive.
+    pub fn survival(&self, neighbours: usize) -> bool {
+        self.survival & 1 << neighbours != 0
+    }
+
+    /// Parses a rule string, returning `None` if it isn't valid.
+    ///
+    /// Accepts "B3/S23", "S23/B3" and the older survival-first "23/3" notation.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (mut birth, mut survival) = (None, None);
+        let parts: Vec<_> = text.split('/').collect();
+        if parts.len() != 2 {
+            return None;
+        }
+        for (idx, part) in parts.iter().enumerate() {
+            let (kind, digits) = match part.chars().next() {
+                Some('B' | 'b') => ('B', &part[1..]),
+                Some('S' | 's') => ('S', &part[1..]),
+                _ => (if idx == 0 { 'S' } else { 'B' }, &part[..]),
+            };
+            let mut mask = 0u16;
+            for c in digits.chars() {
+                match c.to_digit(10) {
+                    Some(n) if n <= 8 => mask |= 1 << n,
+                    _ => return None,
+                }
+            }
+            let slot = if kind == 'B' { &mut birth } else { &mut survival };
+            if slot.replace(mask).is_some() {
+                return None;
+            }
+        }
+        Some(Self { birth: birth?, survival: survival? })
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "B")?;
+        for n in (0..=8).filter(|&n| self.birth(n)) {
+            write!(f, "{}", n)?;
+        }
+        write!(f, "/S")?;
+        for n in (0..=8).filter(|&n| self.survival(n)) {
+            write!(f, "{}", n)?;
+        }
+        Ok(())
+    }
+}
+
+// lets the multi-state engine run two-state rules too
+impl Transition for Rule {
+    fn num_states(&self) -> usize {
+        2
+    }
+
+    fn neighbourhood(&self) -> Neighbourhood {
+        Neighbourhood::Moore
+    }
+
+    fn next_state(&self, cells: &[u8]) -> u8 {
+        let neighbours = cells[1..].iter().filter(|&&state| state != 0).count();
+        let alive = if cells[0] != 0 { self.survival(neighbours) } else { self.birth(neighbours) };
+        alive as u8
+    }
+}
@@ -0,0 +1,218 @@
+use std::{
+    fmt,
+    path::Path,
+};
+use crate::{
+    multi_state::*,
+    rule_table::*,
+    rule_tree::*,
+};
+
+/// An error reading a Golly rule file.
+#[derive(Debug)]
+pub enum RuleFileError {
+    /// The file couldn't be read.
+    Io(std::io::Error),
+    /// The file isn't a valid rule file.
+    Syntax {
+        /// 1-based line number.
+        line: usize,
+        /// What was wrong.
+        message: String,
+    },
+}
+
+impl RuleFileError {
+    pub(crate) fn syntax<S: Into<String>>(line: usize, message: S) -> Self {
+        Self::Syntax { line, message: message.into() }
+    }
+}
+
+impl fmt::Display for RuleFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for RuleFileError {}
+
+impl From<std::io::Error> for RuleFileError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// A state and the colour Golly's @COLORS section gives it.
+pub type StateColor = (usize, (u8, u8, u8));
+
+type Section<'a> = (usize, &'a str, Vec<(usize, &'a str)>);
+
+/// The transition function of a rule file, from either of its sections.
+pub enum RuleEngine {
+    /// An @TABLE section.
+    Table(RuleTable),
+    /// An @TREE section.
+    Tree(RuleTree),
+}
+
+impl Transition for RuleEngine {
+    fn num_states(&self) -> usize {
+        match self {
+            Self::Table(table) => table.num_states(),
+            Self::Tree(tree) => tree.num_states(),
+        }
+    }
+
+    fn neighbourhood(&self) -> Neighbourhood {
+        match self {
+            Self::Table(table) => table.neighbourhood(),
+            Self::Tree(tree) => tree.neighbourhood(),
+        }
+    }
+
+    fn next_state(&self, cells: &[u8]) -> u8 {
+        match self {
+            Self::Table(table) => table.next_state(cells),
+            Self::Tree(tree) => tree.next_state(cells),
+        }
+    }
+}
+
+/// A parsed Golly `.rule` file.
+pub struct RuleFile {
+    /// The name from the @RULE line.
+    pub name: String,
+    /// The rule itself.
+    pub engine: RuleEngine,
+    /// State colours from @COLORS, if any.
+    pub colors: Vec<StateColor>,
+}
+
+impl RuleFile {
+    /// Parses the text of a rule file.
+    pub fn parse(text: &str) -> Result<Self, RuleFileError> {
+        let mut sections: Vec<Section> = Vec::new();
+        for (idx, line) in text.lines().enumerate() {
+            let line_no = idx + 1;
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('@') {
+                sections.push((line_no, line, Vec::new()));
+            } else if let Some((_, _, lines)) = sections.last_mut() {
+                lines.push((line_no, line));
+            } else {
+                return Err(RuleFileError::syntax(line_no, "expected \"@RULE name\""));
+            }
+        }
+
+        let mut name = None;
+        let mut engine = None;
+        let mut color_lines = Vec::new();
+        for (line_no, header, lines) in sections {
+            let (tag, argument) = header.split_once(char::is_whitespace).unwrap_or((header, ""));
+            match tag {
+                "@RULE" => name = Some(argument.trim().to_string()),
+                "@TABLE" | "@TREE" if engine.is_some() => {
+                    return Err(RuleFileError::syntax(line_no, "only one @TABLE or @TREE section is allowed"));
+                }
+                "@TABLE" => engine = Some(RuleEngine::Table(RuleTable::parse(lines.into_iter())?)),
+                "@TREE" => engine = Some(RuleEngine::Tree(RuleTree::parse(lines.into_iter())?)),
+                "@COLORS" => color_lines = lines,
+                _ => {}
+            }
+        }
+        let name = name.ok_or_else(|| RuleFileError::syntax(1, "missing @RULE section"))?;
+        let engine = engine.ok_or_else(|| RuleFileError::syntax(1, "missing @TABLE or @TREE section"))?;
+        let colors = parse_colors(&color_lines, engine.num_states())?;
+        Ok(Self { name, engine, colors })
+    }
+
+    /// Reads and parses a rule file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, RuleFileError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+}
+
+// "state r g b" for a single state, or "r1 g1 b1 r2 g2 b2" for a gradient over states 1 and up
+fn parse_colors(lines: &[(usize, &str)], num_states: usize) -> Result<Vec<StateColor>, RuleFileError> {
+    let mut colors = Vec::new();
+    for &(line_no, line) in lines {
+        let numbers = line.split_whitespace()
+            .map(|n| n.parse::<u8>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| RuleFileError::syntax(line_no, format!("malformed color \"{}\"", line)))?;
+        match numbers[..] {
+            [state, r, g, b] => {
+                if state as usize >= num_states {
+                    return Err(RuleFileError::syntax(line_no, format!("state {} but the rule has {} states", state, num_states)));
+                }
+                colors.push((state as usize, (r, g, b)));
+            }
+            [r1, g1, b1, r2, g2, b2] => {
+                let steps = num_states.saturating_sub(2).max(1) as i32;
+                let lerp = |a: u8, b: u8, i: i32| (a as i32 + (b as i32 - a as i32) * i / steps) as u8;
+                for state in 1..num_states {
+                    let i = state as i32 - 1;
+                    colors.push((state, (lerp(r1, r2, i), lerp(g1, g2, i), lerp(b1, b2, i))));
+                }
+            }
+            _ => return Err(RuleFileError::syntax(line_no, format!("expected \"state r g b\" but found \"{}\"", line))),
+        }
+    }
+    Ok(colors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn syntax_line(result: Result<RuleFile, RuleFileError>) -> usize {
+        match result {
+            Err(RuleFileError::Syntax { line, .. }) => line,
+            Err(e) => panic!("expected a syntax error, got {}", e),
+            Ok(_) => panic!("expected a syntax error"),
+        }
+    }
+
+    #[test]
+    fn parses_sections_and_colors() {
+        let rule_file = RuleFile::parse("# a comment before the rule\n@RULE Spread\n\nSome description.\n\
+            @TABLE\nn_states:3  # states 0 to 2\nneighborhood:vonNeumann\n0,1,0,0,0,1\n\
+            @COLORS\n1 255 0 0\n2 0 0 255\n").unwrap();
+        assert_eq!(rule_file.name, "Spread");
+        assert!(matches!(rule_file.engine, RuleEngine::Table(_)));
+        assert_eq!(rule_file.engine.num_states(), 3);
+        assert_eq!(rule_file.engine.next_state(&[0, 1, 0, 0, 0]), 1);
+        assert_eq!(rule_file.colors, [(1, (255, 0, 0)), (2, (0, 0, 255))]);
+    }
+
+    #[test]
+    fn spreads_a_color_gradient_over_the_live_states() {
+        let rule_file = RuleFile::parse("@RULE Fade\n@TREE\nnum_states=3\nnum_neighbors=4\n\
+            1 0 0 0\n2 0 0 0\n3 1 1 1\n4 2 2 2\n5 3 3 3\n@COLORS\n0 0 0 200 200 200\n").unwrap();
+        assert!(matches!(rule_file.engine, RuleEngine::Tree(_)));
+        assert_eq!(rule_file.colors, [(1, (0, 0, 0)), (2, (200, 200, 200))]);
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        assert_eq!(syntax_line(RuleFile::parse("n_states:2\n@RULE Early")), 1);
+        assert_eq!(syntax_line(RuleFile::parse("@TABLE\nn_states:2\n")), 1);
+        assert_eq!(syntax_line(RuleFile::parse("@RULE Empty\n")), 1);
+        assert_eq!(syntax_line(RuleFile::parse("@RULE Twice\n@TABLE\nn_states:2\n@TABLE\nn_states:2\n")), 4);
+        assert_eq!(syntax_line(RuleFile::parse("@RULE Bad\n@TABLE\nn_states:2\nneighborhood:vonNeumann\n0,1,0,0,1\n")), 5);
+        assert_eq!(syntax_line(RuleFile::parse("@RULE Bad\n@TABLE\nn_states:2\n@COLORS\n2 1 2 3\n")), 5);
+        assert_eq!(syntax_line(RuleFile::parse("@RULE Bad\n@TABLE\nn_states:2\n@COLORS\n1 red\n")), 5);
+        assert_eq!(syntax_line(RuleFile::parse("@RULE Bad\n@TABLE\nn_states:2\n@COLORS\n1 2 3\n")), 5);
+    }
+
+    #[test]
+    fn reports_missing_files() {
+        assert!(matches!(RuleFile::load("no such file.rule"), Err(RuleFileError::Io(_))));
+    }
+}
@@ -0,0 +1,369 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+};
+use crate::{
+    multi_state::*,
+    rule_file::*,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+struct StateSet([u64; 4]);
+
+impl StateSet {
+    fn empty() -> Self {
+        Self([0; 4])
+    }
+
+    fn insert(&mut self, state: usize) {
+        self.0[state / 64] |= 1 << (state % 64);
+    }
+
+    fn contains(&self, state: u8) -> bool {
+        self.0[state as usize / 64] & 1 << (state % 64) != 0
+    }
+
+    fn union(&mut self, other: &Self) {
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a |= b;
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Term {
+    states: StateSet,
+    // occurrences of the same named variable within a transition must take the same value
+    binding: Option<usize>,
+}
+
+struct TableTransition {
+    inputs: Vec<Term>,
+    output: Term,
+}
+
+/// A Golly @TABLE: explicit transitions with variables and symmetries.
+pub struct RuleTable {
+    num_states: usize,
+    neighbourhood: Neighbourhood,
+    permute: bool,
+    symmetries: Vec<Vec<usize>>,
+    transitions: Vec<TableTransition>,
+    cache: RefCell<HashMap<u128, u8>>,
+}
+
+impl RuleTable {
+    /// Parses the lines of an @TABLE section, given with their line numbers.
+    pub fn parse<'a, I: Iterator<Item = (usize, &'a str)>>(lines: I) -> Result<Self, RuleFileError> {
+        let (mut num_states, mut neighbourhood, mut symmetry_name) = (None, None, None);
+        let mut variables: HashMap<String, (usize, StateSet)> = HashMap::new();
+        let mut pending = Vec::new();
+        let mut last_line_no = 0;
+        for (line_no, line) in lines {
+            last_line_no = line_no;
+            if let Some((key, value)) = line.split_once(':') {
+                let value = value.trim();
+                match key.trim() {
+                    "n_states" => num_states = Some(value.parse::<usize>()
+                        .ok()
+                        .filter(|&n| (2..=256).contains(&n))
+                        .ok_or_else(|| RuleFileError::syntax(line_no, format!("invalid n_states \"{}\"", value)))?),
+                    "neighborhood" => neighbourhood = Some(Neighbourhood::from_name(value)
+                        .ok_or_else(|| RuleFileError::syntax(line_no, format!("unknown neighborhood \"{}\"", value)))?),
+                    "symmetries" => symmetry_name = Some((line_no, value.to_string())),
+                    key => return Err(RuleFileError::syntax(line_no, format!("unknown setting \"{}\"", key))),
+                }
+                continue;
+            }
+            let Some(num_states) = num_states else {
+                return Err(RuleFileError::syntax(line_no, "n_states must come before variables and transitions"));
+            };
+            if let Some(definition) = line.strip_prefix("var ") {
+                let Some((name, states)) = definition.split_once('=') else {
+                    return Err(RuleFileError::syntax(line_no, "expected \"var name={...}\""));
+                };
+                let states = parse_states(line_no, states.trim(), num_states, &variables)?;
+                let id = variables.len();
+                variables.insert(name.trim().to_string(), (id, states));
+                continue;
+            }
+            pending.push((line_no, tokenize(line)));
+        }
+
+        let num_states = num_states.ok_or_else(|| RuleFileError::syntax(last_line_no, "missing n_states"))?;
+        let neighbourhood = neighbourhood.unwrap_or(Neighbourhood::Moore);
+        let (permute, symmetries) = match symmetry_name {
+            Some((line_no, name)) => symmetries(neighbourhood, &name)
+                .ok_or_else(|| RuleFileError::syntax(line_no, format!("symmetries \"{}\" not available for {:?}", name, neighbourhood)))?,
+            None => (false, vec![(0..neighbourhood.num_neighbours()).collect()]),
+        };
+
+        let mut transitions = Vec::new();
+        for (line_no, tokens) in pending {
+            if tokens.len() != neighbourhood.num_neighbours() + 2 {
+                return Err(RuleFileError::syntax(line_no, format!(
+                    "transition has {} entries but {:?} needs {}", tokens.len(), neighbourhood, neighbourhood.num_neighbours() + 2
+                )));
+            }
+            let mut terms = Vec::new();
+            for token in &tokens {
+                terms.push(match variables.get(token.as_str()) {
+                    Some(&(id, states)) => Term { states, binding: Some(id) },
+                    None => Term { states: parse_states(line_no, token, num_states, &variables)?, binding: None },
+                });
+            }
+            let output = terms.pop().unwrap();
+            if output.binding.is_some_and(|id| !terms.iter().any(|term| term.binding == Some(id))) {
+                return Err(RuleFileError::syntax(line_no, "output variable is not bound by an input"));
+            }
+            if output.binding.is_none() && output.states.0.iter().map(|w| w.count_ones()).sum::<u32>() != 1 {
+                return Err(RuleFileError::syntax(line_no, "output must be a single state or a bound variable"));
+            }
+            transitions.push(TableTransition { inputs: terms, output });
+        }
+        Ok(Self {
+            num_states,
+            neighbourhood,
+            permute,
+            symmetries,
+            transitions,
+            cache: RefCell::new(HashMap::new()),
+        })
+    }
+
+    fn evaluate(&self, cells: &[u8]) -> u8 {
+        for transition in &self.transitions {
+            let mut bound = HashMap::new();
+            if !bind(&transition.inputs[0], cells[0], &mut bound) {
+                continue;
+            }
+            let matched = if self.permute {
+                let mut used = vec![false; cells.len() - 1];
+                match_permuted(&transition.inputs[1..], &cells[1..], &mut used, &mut bound)
+            } else {
+                self.symmetries.iter().find_map(|permutation| {
+                    let mut bound = bound.clone();
+                    permutation.iter().enumerate()
+                        .all(|(idx, &cell_idx)| bind(&transition.inputs[1 + idx], cells[1 + cell_idx], &mut bound))
+                        .then_some(bound)
+                })
+            };
+            if let Some(bound) = matched {
+                return match transition.output.binding {
+                    Some(id) => bound[&id],
+                    None => (0..self.num_states).find(|&s| transition.output.states.contains(s as u8)).unwrap() as u8,
+                };
+            }
+        }
+        cells[0]
+    }
+}
+
+impl Transition for RuleTable {
+    fn num_states(&self) -> usize {
+        self.num_states
+    }
+
+    fn neighbourhood(&self) -> Neighbourhood {
+        self.neighbourhood
+    }
+
+    fn next_state(&self, cells: &[u8]) -> u8 {
+        let key = cells.iter().fold(0u128, |key, &state| key << 8 | state as u128);
+        if let Some(&state) = self.cache.borrow().get(&key) {
+            return state;
+        }
+        let state = self.evaluate(cells);
+        self.cache.borrow_mut().insert(key, state);
+        state
+    }
+}
+
+fn bind(term: &Term, state: u8, bound: &mut HashMap<usize, u8>) -> bool {
+    if !term.states.contains(state) {
+        return false;
+    }
+    match term.binding {
+        Some(id) => *bound.entry(id).or_insert(state) == state,
+        None => true,
+    }
+}
+
+// with permute symmetry any assignment of the neighbours to the transition's positions will do
+fn match_permuted(terms: &[Term], cells: &[u8], used: &mut [bool], bound: &mut HashMap<usize, u8>) -> Option<HashMap<usize, u8>> {
+    let Some((term, rest)) = terms.split_first() else {
+        return Some(bound.clone());
+    };
+    for idx in 0..cells.len() {
+        if used[idx] {
+            continue;
+        }
+        let mut trial = bound.clone();
+        if bind(term, cells[idx], &mut trial) {
+            used[idx] = true;
+            let result = match_permuted(rest, cells, used, &mut trial);
+            used[idx] = false;
+            if result.is_some() {
+                return result;
+            }
+        }
+    }
+    None
+}
+
+fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut depth = 0;
+    let compact = !line.contains(',') && !line.contains(char::is_whitespace);
+    for c in line.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 && (c == ',' || c.is_whitespace()) {
+            if !token.is_empty() {
+                tokens.push(std::mem::take(&mut token));
+            }
+        } else if compact && depth == 0 && c != '}' {
+            tokens.push(c.to_string());
+        } else {
+            token.push(c);
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    tokens
+}
+
+fn parse_states(line_no: usize, text: &str, num_states: usize, variables: &HashMap<String, (usize, StateSet)>) -> Result<StateSet, RuleFileError> {
+    let mut states = StateSet::empty();
+    let items = match text.strip_prefix('{').and_then(|t| t.strip_suffix('}')) {
+        Some(inner) => inner.split(',').map(|item| item.trim()).collect(),
+        None => vec![text],
+    };
+    for item in items {
+        if let Some((_, other)) = variables.get(item) {
+            states.union(other);
+        } else {
+            match item.parse::<usize>() {
+                Ok(state) if state < num_states => states.insert(state),
+                _ => return Err(RuleFileError::syntax(line_no, format!("\"{}\" is neither a state nor a variable", item))),
+            }
+        }
+    }
+    Ok(states)
+}
+
+fn symmetries(neighbourhood: Neighbourhood, name: &str) -> Option<(bool, Vec<Vec<usize>>)> {
+    let n = neighbourhood.num_neighbours();
+    let rotations = |k: usize| n.is_multiple_of(k).then(|| (0..k).map(|r| r * n / k).collect::<Vec<_>>());
+    let (steps, reflect) = match name {
+        "none" => (vec![0], false),
+        "permute" => return Some((true, vec![])),
+        "reflect" | "reflect_horizontal" => (vec![0], true),
+        "rotate2" => (rotations(2)?, false),
+        "rotate3" => (rotations(3)?, false),
+        "rotate4" => (rotations(4)?, false),
+        "rotate6" => (rotations(6)?, false),
+        "rotate8" => (rotations(8)?, false),
+        "rotate4reflect" => (rotations(4)?, true),
+        "rotate6reflect" => (rotations(6)?, true),
+        "rotate8reflect" => (rotations(8)?, true),
+        _ => return None,
+    };
+    let mut permutations = Vec::new();
+    for mirrored in if reflect { vec![false, true] } else { vec![false] } {
+        for &step in &steps {
+            permutations.push((0..n).map(|j| {
+                let j = match (mirrored, neighbourhood) {
+                    (false, _) => j,
+                    (true, Neighbourhood::OneDimensional) => n - 1 - j,
+                    (true, _) => (n - j) % n,
+                };
+                (j + step) % n
+            }).collect());
+        }
+    }
+    Some((false, permutations))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(text: &str) -> Result<RuleTable, RuleFileError> {
+        RuleTable::parse(text.lines().enumerate().map(|(idx, line)| (idx + 1, line)))
+    }
+
+    fn syntax_line(result: Result<RuleTable, RuleFileError>) -> usize {
+        match result {
+            Err(RuleFileError::Syntax { line, .. }) => line,
+            Err(e) => panic!("expected a syntax error, got {}", e),
+            Ok(_) => panic!("expected a syntax error"),
+        }
+    }
+
+    const WIREWORLD: &str = "n_states:4\nneighborhood:Moore\nsymmetries:permute\n\
+        var a={0,1,2,3}\nvar b=a\nvar c=a\nvar d=a\nvar e=a\nvar f=a\nvar g=a\nvar h=a\n\
+        var i={0,2,3}\nvar j=i\nvar k=i\nvar l=i\nvar m=i\nvar n=i\nvar o=i\n\
+        1,a,b,c,d,e,f,g,h,2\n2,a,b,c,d,e,f,g,h,3\n3,1,i,j,k,l,m,n,o,1\n3,1,1,i,j,k,l,m,n,1";
+
+    #[test]
+    fn runs_wireworld_with_permute_symmetry() {
+        let wireworld = table(WIREWORLD).unwrap();
+        assert_eq!(wireworld.num_states(), 4);
+        assert_eq!(wireworld.neighbourhood(), Neighbourhood::Moore);
+        assert_eq!(wireworld.next_state(&[1, 0, 0, 0, 0, 0, 0, 0, 0]), 2);
+        assert_eq!(wireworld.next_state(&[2, 1, 1, 1, 0, 0, 0, 0, 0]), 3);
+        assert_eq!(wireworld.next_state(&[3, 0, 0, 0, 0, 0, 1, 0, 3]), 1);
+        assert_eq!(wireworld.next_state(&[3, 0, 1, 0, 0, 0, 1, 0, 3]), 1);
+        assert_eq!(wireworld.next_state(&[3, 1, 1, 0, 0, 0, 1, 0, 3]), 3);
+        assert_eq!(wireworld.next_state(&[3, 2, 0, 0, 0, 0, 0, 0, 3]), 3);
+        assert_eq!(wireworld.next_state(&[0, 1, 1, 1, 1, 1, 1, 1, 1]), 0);
+    }
+
+    #[test]
+    fn applies_rotations_only_when_asked() {
+        let rotated = table("n_states:2\nneighborhood:vonNeumann\nsymmetries:rotate4\n0,1,0,0,0,1").unwrap();
+        let fixed = table("n_states:2\nneighborhood:vonNeumann\nsymmetries:none\n0,1,0,0,0,1").unwrap();
+        for cells in [[0, 1, 0, 0, 0], [0, 0, 1, 0, 0], [0, 0, 0, 0, 1]] {
+            assert_eq!(rotated.next_state(&cells), 1, "{:?}", cells);
+        }
+        assert_eq!(rotated.next_state(&[0, 1, 1, 0, 0]), 0);
+        assert_eq!(fixed.next_state(&[0, 1, 0, 0, 0]), 1);
+        assert_eq!(fixed.next_state(&[0, 0, 1, 0, 0]), 0);
+    }
+
+    #[test]
+    fn binds_repeated_variables() {
+        let copy = table("n_states:3\nneighborhood:vonNeumann\nvar a={1,2}\n0,a,a,0,0,a").unwrap();
+        assert_eq!(copy.next_state(&[0, 2, 2, 0, 0]), 2);
+        assert_eq!(copy.next_state(&[0, 1, 1, 0, 0]), 1);
+        assert_eq!(copy.next_state(&[0, 1, 2, 0, 0]), 0);
+    }
+
+    #[test]
+    fn reads_compact_transitions() {
+        let compact = table("n_states:2\nneighborhood:vonNeumann\n010001").unwrap();
+        assert_eq!(compact.next_state(&[0, 1, 0, 0, 0]), 1);
+        assert_eq!(compact.next_state(&[0, 0, 1, 0, 0]), 0);
+    }
+
+    #[test]
+    fn rejects_malformed_tables() {
+        assert_eq!(syntax_line(table("n_states:1")), 1);
+        assert_eq!(syntax_line(table("n_states:2\nneighborhood:triangular")), 2);
+        assert_eq!(syntax_line(table("n_states:2\ncolours:many")), 2);
+        assert_eq!(syntax_line(table("var a={0,1}\nn_states:2")), 1);
+        assert_eq!(syntax_line(table("n_states:2\nvar a")), 2);
+        assert_eq!(syntax_line(table("n_states:2\nvar a={0,2}")), 2);
+        assert_eq!(syntax_line(table("n_states:2\nneighborhood:Moore\nsymmetries:rotate3")), 3);
+        assert_eq!(syntax_line(table("n_states:2\nneighborhood:vonNeumann\n0,1,0,0,1")), 3);
+        assert_eq!(syntax_line(table("n_states:2\nneighborhood:vonNeumann\nvar a={0,1}\n0,1,0,0,0,a")), 4);
+        assert_eq!(syntax_line(table("n_states:2\nneighborhood:vonNeumann\n0,1,0,0,0,{0,1}")), 3);
+        assert_eq!(syntax_line(table("neighborhood:Moore\n")), 1);
+    }
+}
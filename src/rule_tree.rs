@@ -0,0 +1,149 @@
+use crate::{
+    multi_state::*,
+    rule_file::*,
+};
+
+/// A Golly @TREE: the transition function as a decision tree over the neighbourhood.
+pub struct RuleTree {
+    num_states: usize,
+    neighbourhood: Neighbourhood,
+    nodes: Vec<Vec<usize>>,
+}
+
+impl RuleTree {
+    /// Parses the lines of an @TREE section, given with their line numbers.
+    pub fn parse<'a, I: Iterator<Item = (usize, &'a str)>>(lines: I) -> Result<Self, RuleFileError> {
+        let (mut num_states, mut num_neighbours, mut num_nodes) = (None, None, None);
+        let mut levels: Vec<usize> = Vec::new();
+        let mut nodes: Vec<Vec<usize>> = Vec::new();
+        let mut last_line_no = 0;
+        for (line_no, line) in lines {
+            last_line_no = line_no;
+            if let Some((key, value)) = line.split_once('=') {
+                let value = value.trim().parse::<usize>()
+                    .map_err(|_| RuleFileError::syntax(line_no, format!("invalid value for {}", key.trim())))?;
+                match key.trim() {
+                    "num_states" => num_states = Some(value),
+                    "num_neighbors" => num_neighbours = Some(value),
+                    "num_nodes" => num_nodes = Some(value),
+                    key => return Err(RuleFileError::syntax(line_no, format!("unknown setting \"{}\"", key))),
+                }
+                continue;
+            }
+            let (Some(num_states), Some(num_neighbours)) = (num_states, num_neighbours) else {
+                return Err(RuleFileError::syntax(line_no, "num_states and num_neighbors must come before the nodes"));
+            };
+            let numbers = line.split_whitespace()
+                .map(|n| n.parse::<usize>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| RuleFileError::syntax(line_no, format!("malformed node \"{}\"", line)))?;
+            let Some((&level, children)) = numbers.split_first() else {
+                continue;
+            };
+            if children.len() != num_states {
+                return Err(RuleFileError::syntax(line_no, format!("node has {} entries but num_states is {}", children.len(), num_states)));
+            }
+            if level == 0 || level > num_neighbours + 1 {
+                return Err(RuleFileError::syntax(line_no, format!("invalid node level {}", level)));
+            }
+            for &child in children {
+                let valid = if level == 1 {
+                    child < num_states
+                } else {
+                    child < nodes.len() && levels[child] == level - 1
+                };
+                if !valid {
+                    return Err(RuleFileError::syntax(line_no, format!("invalid entry {} in level {} node", child, level)));
+                }
+            }
+            levels.push(level);
+            nodes.push(children.to_vec());
+        }
+        let (Some(num_states), Some(num_neighbours)) = (num_states, num_neighbours) else {
+            return Err(RuleFileError::syntax(last_line_no, "missing num_states or num_neighbors"));
+        };
+        let neighbourhood = match num_neighbours {
+            4 => Neighbourhood::VonNeumann,
+            8 => Neighbourhood::Moore,
+            n => return Err(RuleFileError::syntax(last_line_no, format!("unsupported num_neighbors={}", n))),
+        };
+        if num_states > 256 {
+            return Err(RuleFileError::syntax(last_line_no, format!("too many states ({})", num_states)));
+        }
+        if num_nodes.is_some_and(|n| n != nodes.len()) {
+            return Err(RuleFileError::syntax(last_line_no, format!("num_nodes={} but {} nodes given", num_nodes.unwrap(), nodes.len())));
+        }
+        if levels.last() != Some(&(num_neighbours + 1)) {
+            return Err(RuleFileError::syntax(last_line_no, "the last node must be the root"));
+        }
+        Ok(Self { num_states, neighbourhood, nodes })
+    }
+}
+
+impl Transition for RuleTree {
+    fn num_states(&self) -> usize {
+        self.num_states
+    }
+
+    fn neighbourhood(&self) -> Neighbourhood {
+        self.neighbourhood
+    }
+
+    fn next_state(&self, cells: &[u8]) -> u8 {
+        // RuleTree variable order is nw, ne, sw, se, n, w, e, s, c (or n, w, e, s, c)
+        let order: &[usize] = match self.neighbourhood {
+            Neighbourhood::Moore => &[8, 2, 6, 4, 1, 7, 3, 5, 0],
+            _ => &[1, 4, 2, 3, 0],
+        };
+        let mut value = self.nodes.len() - 1;
+        for &idx in order {
+            value = self.nodes[value][cells[idx] as usize];
+        }
+        value as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(text: &str) -> Result<RuleTree, RuleFileError> {
+        RuleTree::parse(text.lines().enumerate().map(|(idx, line)| (idx + 1, line)))
+    }
+
+    fn syntax_line(result: Result<RuleTree, RuleFileError>) -> usize {
+        match result {
+            Err(RuleFileError::Syntax { line, .. }) => line,
+            Err(e) => panic!("expected a syntax error, got {}", e),
+            Ok(_) => panic!("expected a syntax error"),
+        }
+    }
+
+    // a cell turns on when its north neighbour is on, and then stays on
+    const NORTH: &str = "num_states=2\nnum_neighbors=4\nnum_nodes=9\n\
+        1 0 1\n1 1 1\n2 0 0\n2 1 1\n3 2 2\n3 3 3\n4 4 4\n4 5 5\n5 6 7";
+
+    #[test]
+    fn follows_the_tree_in_golly_order() {
+        let north = tree(NORTH).unwrap();
+        assert_eq!(north.num_states(), 2);
+        assert_eq!(north.neighbourhood(), Neighbourhood::VonNeumann);
+        assert_eq!(north.next_state(&[0, 1, 0, 0, 0]), 1);
+        assert_eq!(north.next_state(&[0, 0, 1, 1, 1]), 0);
+        assert_eq!(north.next_state(&[1, 0, 0, 0, 0]), 1);
+    }
+
+    #[test]
+    fn rejects_malformed_trees() {
+        assert_eq!(syntax_line(tree("num_states=x")), 1);
+        assert_eq!(syntax_line(tree("num_states=2\nnum_colours=2")), 2);
+        assert_eq!(syntax_line(tree("num_states=2\n1 0 1")), 2);
+        assert_eq!(syntax_line(tree("num_states=2\nnum_neighbors=4\n1 0")), 3);
+        assert_eq!(syntax_line(tree("num_states=2\nnum_neighbors=4\n6 0 0")), 3);
+        assert_eq!(syntax_line(tree("num_states=2\nnum_neighbors=4\n1 0 2")), 3);
+        assert_eq!(syntax_line(tree("num_states=2\nnum_neighbors=4\n1 0 1\n3 0 0")), 4);
+        assert_eq!(syntax_line(tree("num_states=2\nnum_neighbors=6\n1 0 1")), 3);
+        assert_eq!(syntax_line(tree("num_states=2\nnum_neighbors=4\n1 0 1\n2 0 0")), 4);
+        assert_eq!(syntax_line(tree(&NORTH.replace("num_nodes=9", "num_nodes=8"))), 12);
+    }
+}
@@ -0,0 +1,245 @@
+use std::path::{
+    Path,
+    PathBuf,
+};
+use crate::{
+    life_cell::*,
+    pattern::*,
+    rule::*,
+    rle,
+};
+
+/// First line of every session file.
+pub const HEADER: &str = "#life-rs session 1";
+
+/// Where the viewer keeps its autosave: `life-rs/autosave.session` under the
+/// platform's state directory, or `None` if there isn't one.
+pub fn autosave_path() -> Option<PathBuf> {
+    let state_dir = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state")))
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))?;
+    Some(state_dir.join(env!("CARGO_PKG_NAME")).join("autosave.session"))
+}
+
+/// A snapshot of a running simulation, stored as a few "key = value" lines
+/// followed by the live cells in RLE.
+#[derive(Debug, Clone)]
+pub struct Session {
+    /// The rule being run.
+    pub rule: Rule,
+    /// Generation number at the time of the snapshot.
+    pub generation: i32,
+    /// Position of the viewer's scroll, in pixels.
+    pub view_pos: (i32, i32),
+    /// Frames the viewer waits between generations.
+    pub wait: i32,
+    /// Whether the simulation was paused.
+    pub pause: bool,
+    /// Path of the rule file being run instead of `rule`, if any.
+    pub rule_file: Option<String>,
+    world_size: (usize, usize),
+    origin: (usize, usize),
+    pattern: Pattern,
+}
+
+impl Session {
+    /// Takes a snapshot of `world`, run under the rule file at `rule_file` if
+    /// given, and the viewer state.
+    pub fn capture(world: &World, rule_file: Option<&str>, generation: i32, view_pos: (i32, i32), wait: i32, pause: bool) -> Self {
+        let origin = world.bounding_box().map_or((0, 0), |(pos, _)| pos);
+        Self {
+            rule: world.rule(),
+            generation,
+            view_pos,
+            wait,
+            pause,
+            rule_file: rule_file.map(str::to_string),
+            world_size: world.size(),
+            origin,
+            pattern: Pattern::from_world(world, None),
+        }
+    }
+
+    /// Rebuilds the world the snapshot was taken from.
+    pub fn world(&self) -> World {
+        let mut world = World::new(self.world_size);
+        world.set_rule(self.rule);
+        self.pattern.place(&mut world, self.origin);
+        world
+    }
+
+    /// Formats the session as it is saved.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\n", HEADER);
+        text += &format!("world = {} {}\n", self.world_size.0, self.world_size.1);
+        text += &format!("rule = {}\n", self.rule);
+        if let Some(path) = &self.rule_file {
+            text += &format!("rule-file = {}\n", path);
+        }
+        text += &format!("generation = {}\n", self.generation);
+        text += &format!("view = {} {}\n", self.view_pos.0, self.view_pos.1);
+        text += &format!("wait = {}\n", self.wait);
+        text += &format!("pause = {}\n", self.pause);
+        text += &format!("origin = {} {}\n", self.origin.0, self.origin.1);
+        text += &rle::write(&self.pattern);
+        text
+    }
+
+    /// Parses a saved session.
+    pub fn parse(text: &str) -> Result<Self, PatternError> {
+        let mut lines = text.lines().enumerate().map(|(idx, line)| (idx + 1, line.trim()));
+        if lines.next().map(|(_, line)| line) != Some(HEADER) {
+            return Err(PatternError::syntax(1, format!("expected \"{}\" header", HEADER)));
+        }
+        let mut session = Self {
+            rule: Rule::default(),
+            generation: 0,
+            view_pos: (0, 0),
+            wait: 8,
+            pause: true,
+            rule_file: None,
+            world_size: (0, 0),
+            origin: (0, 0),
+            pattern: Pattern::default(),
+        };
+        let mut body_line_no = None;
+        for (line_no, line) in lines.by_ref() {
+            if line.starts_with('#') || line.starts_with('x') {
+                body_line_no = Some(line_no);
+                break;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(PatternError::syntax(line_no, "expected \"key = value\""));
+            };
+            let value = value.trim();
+            let invalid = || PatternError::syntax(line_no, format!("invalid {} \"{}\"", key.trim(), value));
+            let pair = || {
+                let numbers: Vec<_> = value.split_whitespace().collect();
+                match numbers[..] {
+                    [a, b] => Some((a, b)),
+                    _ => None,
+                }
+            };
+            match key.trim() {
+                "world" => {
+                    let (w, h) = pair().ok_or_else(invalid)?;
+                    session.world_size = (w.parse().map_err(|_| invalid())?, h.parse().map_err(|_| invalid())?);
+                    let valid = |size, min| (min..=MAX_WORLD_SIZE).contains(&size);
+                    if !valid(session.world_size.0, MIN_WORLD_SIZE.0) || !valid(session.world_size.1, MIN_WORLD_SIZE.1) {
+                        return Err(PatternError::syntax(line_no, format!(
+                            "world must be from {}x{} to {}x{}", MIN_WORLD_SIZE.0, MIN_WORLD_SIZE.1, MAX_WORLD_SIZE, MAX_WORLD_SIZE
+                        )));
+                    }
+                }
+                "rule" => session.rule = Rule::parse(value).ok_or_else(invalid)?,
+                "rule-file" => {
+                    if value.is_empty() {
+                        return Err(invalid());
+                    }
+                    session.rule_file = Some(value.to_string());
+                }
+                "generation" => session.generation = value.parse().map_err(|_| invalid())?,
+                "view" => {
+                    let (x, y) = pair().ok_or_else(invalid)?;
+                    session.view_pos = (x.parse().map_err(|_| invalid())?, y.parse().map_err(|_| invalid())?);
+                }
+                "wait" => session.wait = value.parse().ok().filter(|&wait| wait > 0).ok_or_else(invalid)?,
+                "pause" => session.pause = value.parse().map_err(|_| invalid())?,
+                "origin" => {
+                    let (x, y) = pair().ok_or_else(invalid)?;
+                    session.origin = (x.parse().map_err(|_| invalid())?, y.parse().map_err(|_| invalid())?);
+                }
+                key => return Err(PatternError::syntax(line_no, format!("unknown session key \"{}\"", key))),
+            }
+        }
+        if session.world_size.0 == 0 || session.world_size.1 == 0 {
+            return Err(PatternError::syntax(1, "missing world size"));
+        }
+        let Some(body_line_no) = body_line_no else {
+            return Err(PatternError::syntax(text.lines().count(), "missing pattern"));
+        };
+        let body: Vec<_> = text.lines().skip(body_line_no - 1).collect();
+        session.pattern = rle::parse(&body.join("\n")).map_err(|e| match e {
+            PatternError::Syntax { line, message } => PatternError::Syntax { line: line + body_line_no - 1, message },
+            e => e,
+        })?;
+        // placed cells would otherwise wrap around to the other side
+        let (w, h) = session.world_size;
+        session.pattern.check_fits((w.saturating_sub(session.origin.0), h.saturating_sub(session.origin.1)))?;
+        Ok(session)
+    }
+
+    /// Writes the session to `path`, creating its directory if needed.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), PatternError> {
+        if let Some(dir) = path.as_ref().parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.to_text())?;
+        Ok(())
+    }
+
+    /// Reads and parses a session file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, PatternError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn syntax_line<T>(result: Result<T, PatternError>) -> Option<usize> {
+        match result {
+            Err(PatternError::Syntax { line, .. }) => Some(line),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn round_trips_through_text() {
+        let mut world = World::new((40, 30));
+        world.set_rule(Rule::parse("B36/S23").unwrap());
+        for pos in [(11, 10), (12, 11), (10, 12), (11, 12), (12, 12)] {
+            world.set_cell(pos, CellState::Live);
+        }
+        let session = Session::parse(&Session::capture(&world, Some("rules/wire.rule"), 42, (8, -16), 3, false).to_text()).unwrap();
+        assert_eq!(session.rule, world.rule());
+        assert_eq!((session.generation, session.view_pos, session.wait, session.pause), (42, (8, -16), 3, false));
+        assert_eq!(session.rule_file.as_deref(), Some("rules/wire.rule"));
+        let restored = session.world();
+        assert_eq!(restored.size(), (40, 30));
+        assert_eq!(restored.live_cells().collect::<Vec<_>>(), world.live_cells().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn omits_the_rule_file_when_none_is_running() {
+        let text = Session::capture(&World::new((16, 16)), None, 0, (0, 0), 8, true).to_text();
+        assert!(!text.contains("rule-file"));
+        assert_eq!(Session::parse(&text).unwrap().rule_file, None);
+    }
+
+    #[test]
+    fn rejects_world_sizes_out_of_range() {
+        let text = |size: &str| format!("{}\nworld = {}\nx = 1, y = 1\no!\n", HEADER, size);
+        assert_eq!(syntax_line(Session::parse(&text("15 16"))), Some(2));
+        assert_eq!(syntax_line(Session::parse(&text(&format!("16 {}", MAX_WORLD_SIZE + 1)))), Some(2));
+        assert_eq!(syntax_line(Session::parse(&text("100000000000 100000000000"))), Some(2));
+        assert!(Session::parse(&text(&format!("{} 16", MAX_WORLD_SIZE))).is_ok());
+    }
+
+    #[test]
+    fn rejects_patterns_that_would_wrap() {
+        let text = |origin: &str| format!("{}\nworld = 16 16\norigin = {}\nx = 3, y = 1\nooo!\n", HEADER, origin);
+        assert!(Session::parse(&text("13 15")).is_ok());
+        assert!(matches!(Session::parse(&text("14 0")), Err(PatternError::TooLarge { size: (3, 1), limit: (2, 16) })));
+        assert!(matches!(Session::parse(&text("0 16")), Err(PatternError::TooLarge { .. })));
+        assert!(matches!(Session::parse(&text("18446744073709551615 0")), Err(PatternError::TooLarge { .. })));
+    }
+
+    #[test]
+    fn rejects_an_empty_rule_file() {
+        assert_eq!(syntax_line(Session::parse(&format!("{}\nworld = 16 16\nrule-file =\nx = 1, y = 1\no!\n", HEADER))), Some(3));
+    }
+}
@@ -0,0 +1,108 @@
+use crate::life_cell::*;
+
+/// The density of a soup when none is given.
+pub const RANDOM_DENSITY: f64 = 0.5;
+
+/// The symmetry imposed on a random [`Soup`].
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum Symmetry {
+    /// No symmetry.
+    #[default]
+    C1,
+    /// Unchanged by a half turn.
+    C2,
+    /// Unchanged by a quarter turn; the filled area is made square.
+    C4,
+    /// Mirrored left to right.
+    D2,
+    /// Mirrored left to right and top to bottom.
+    D4,
+    /// Mirrored along both axes and both diagonals; the filled area is made square.
+    D8,
+}
+
+impl Symmetry {
+    /// Every symmetry, in the order [`Symmetry::next`] cycles through them.
+    pub const ALL: [Self; 6] = [Self::C1, Self::C2, Self::C4, Self::D2, Self::D4, Self::D8];
+
+    /// Looks a symmetry up by name, ignoring case ("c1", "D8", ...).
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|symmetry| format!("{:?}", symmetry).eq_ignore_ascii_case(name))
+    }
+
+    /// The symmetry after this one in [`Symmetry::ALL`], wrapping around.
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|&symmetry| symmetry == self).unwrap();
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    fn is_square(self) -> bool {
+        matches!(self, Self::C4 | Self::D8)
+    }
+
+    fn representative(self, pos: (usize, usize), size: (usize, usize)) -> (usize, usize) {
+        let (x, y) = pos;
+        let (rx, ry) = (size.0 - 1 - x, size.1 - 1 - y);
+        match self {
+            Self::C1 => pos,
+            Self::C2 => pos.min((rx, ry)),
+            Self::C4 => pos.min((ry, x)).min((rx, ry)).min((y, rx)),
+            Self::D2 => pos.min((rx, y)),
+            Self::D4 => pos.min((rx, y)).min((x, ry)).min((rx, ry)),
+            Self::D8 => pos.min((rx, y)).min((x, ry)).min((rx, ry))
+                .min((y, x)).min((ry, x)).min((y, rx)).min((ry, rx)),
+        }
+    }
+}
+
+/// A reproducible random fill: the same seed, density and symmetry always
+/// give the same cells, whatever rectangle is filled.
+#[derive(Debug, Clone, Copy)]
+pub struct Soup {
+    /// Seed for the random cells.
+    pub seed: u64,
+    /// Chance of each cell being alive, from 0 to 1.
+    pub density: f64,
+    /// Symmetry of the filled rectangle.
+    pub symmetry: Symmetry,
+}
+
+impl Soup {
+    /// Creates a soup; `density` is clamped to 0..=1.
+    pub fn new(seed: u64, density: f64, symmetry: Symmetry) -> Self {
+        Self {
+            seed,
+            density: density.clamp(0.0, 1.0),
+            symmetry,
+        }
+    }
+
+    /// Overwrites the cells in `rect` (position, size) and returns the change in population.
+    pub fn fill(&self, world: &mut World, rect: ((usize, usize), (usize, usize))) -> i32 {
+        let (pos, mut size) = rect;
+        if self.symmetry.is_square() {
+            let side = size.0.min(size.1);
+            size = (side, side);
+        }
+        let mut growth = 0;
+        for y in 0..size.1 {
+            for x in 0..size.0 {
+                let (rx, ry) = self.symmetry.representative((x, y), size);
+                let state = if self.random((rx, ry)) < self.density { CellState::Live } else { CellState::Dead };
+                growth += world.set_cell((pos.0 + x, pos.1 + y), state);
+            }
+        }
+        growth
+    }
+
+    fn random(&self, pos: (usize, usize)) -> f64 {
+        // splitmix64 keyed by seed and position, so any rectangle reproduces from the seed alone
+        let mut z = self.seed
+            .wrapping_add((pos.0 as u64).wrapping_mul(0x9e3779b97f4a7c15))
+            .wrapping_add((pos.1 as u64).wrapping_mul(0xc2b2ae3d27d4eb4f));
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::BTreeSet;
+use std::io::{
+    self,
+    Write,
+};
+use crate::{
+    life_cell::*,
+    pattern::*,
+};
+
+/// What a [`Recorder`] writes for each recorded generation.
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum StreamFormat {
+    /// One line per record: the generation followed by `+x,y` for each birth
+    /// and `-x,y` for each death since the previous record.
+    #[default]
+    Diff,
+    /// The whole pattern in a pattern file format, headed by a comment giving
+    /// the generation and where its top-left corner lies in the world.
+    Frame(Format),
+}
+
+/// Writes generations of a run to `out` as they happen.
+///
+/// Call [`Recorder::start`] once, [`Recorder::record`] after every generation
+/// and [`Recorder::finish`] at the end. Every `every`th generation after the
+/// start is written, and so is the last one.
+pub struct Recorder<W: Write> {
+    out: W,
+    format: StreamFormat,
+    every: u32,
+    start: i32,
+    last_written: i32,
+    // cells flipped an odd number of times since the last record, as (y, x)
+    toggled: BTreeSet<(usize, usize)>,
+}
+
+impl<W: Write> Recorder<W> {
+    /// Creates a recorder writing every `every`th generation (at least 1).
+    pub fn new(out: W, format: StreamFormat, every: u32) -> Self {
+        Self { out, format, every: every.max(1), start: 0, last_written: 0, toggled: BTreeSet::new() }
+    }
+
+    /// Writes the starting state. A diff stream begins with a `#` header line
+    /// giving the world size and rule, then every live cell as a birth.
+    pub fn start(&mut self, world: &World, generation: i32) -> io::Result<()> {
+        (self.start, self.last_written) = (generation, generation);
+        match self.format {
+            StreamFormat::Diff => {
+                let (width, height) = world.size();
+                writeln!(self.out, "#life-rs diff {}x{} {}", width, height, world.rule())?;
+                self.toggled = world.live_cells().map(|(x, y)| (y, x)).collect();
+                self.write_diff(world, generation)
+            }
+            StreamFormat::Frame(format) => self.write_frame(world, generation, format),
+        }
+    }
+
+    /// Notes the changes made by the last [`World::update_world`] and writes
+    /// the generation if it is due.
+    pub fn record(&mut self, world: &World, generation: i32) -> io::Result<()> {
+        if self.format == StreamFormat::Diff {
+            for (x, y) in world.changes() {
+                if !self.toggled.insert((y, x)) {
+                    self.toggled.remove(&(y, x));
+                }
+            }
+        }
+        if ((generation - self.start) as u32).is_multiple_of(self.every) {
+            self.write(world, generation)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the final generation unless it was just written, and flushes.
+    pub fn finish(&mut self, world: &World, generation: i32) -> io::Result<()> {
+        if generation != self.last_written {
+            self.write(world, generation)?;
+        }
+        self.out.flush()
+    }
+
+    fn write(&mut self, world: &World, generation: i32) -> io::Result<()> {
+        self.last_written = generation;
+        match self.format {
+            StreamFormat::Diff => self.write_diff(world, generation),
+            StreamFormat::Frame(format) => self.write_frame(world, generation, format),
+        }
+    }
+
+    fn write_diff(&mut self, world: &World, generation: i32) -> io::Result<()> {
+        let mut line = generation.to_string();
+        for (y, x) in std::mem::take(&mut self.toggled) {
+            let sign = if world.read_cell((x, y)).state == CellState::Live { '+' } else { '-' };
+            line += &format!(" {}{},{}", sign, x, y);
+        }
+        writeln!(self.out, "{}", line)
+    }
+
+    fn write_frame(&mut self, world: &World, generation: i32, format: Format) -> io::Result<()> {
+        let mut pattern = Pattern::from_world(world, None);
+        let (x, y) = world.bounding_box().map_or((0, 0), |(pos, _)| pos);
+        pattern.comments.push(format!("generation {} at {},{}", generation, x, y));
+        write!(self.out, "{}", write(&pattern, format))
+    }
+}
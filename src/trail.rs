@@ -0,0 +1,41 @@
+use crate::life_cell::*;
+
+/// When each cell of a world last died, for drawing fading marks where
+/// cells were recently alive.
+pub struct Trail {
+    size: (usize, usize),
+    length: i32,
+    codes: Vec<char>,
+    died_at: Vec<i32>,
+}
+impl Trail {
+    /// Creates a trail for a world of `size` whose marks last `length`
+    /// generations, going through `codes` as they fade.
+    pub fn new(size: (usize, usize), length: i32, codes: &[char]) -> Self {
+        Self {
+            size,
+            length: length.max(1),
+            codes: codes.to_vec(),
+            died_at: vec![i32::MIN; size.0 * size.1],
+        }
+    }
+
+    /// Notes the cells that died in the generation just run.
+    pub fn record(&mut self, world: &World, generation: i32) {
+        for (x, y) in world.changes() {
+            if world.read_cell((x, y)).state == CellState::Dead {
+                self.died_at[x + y * self.size.0] = generation;
+            }
+        }
+    }
+
+    /// The mark to draw at `pos` in `generation`, if a cell died there recently.
+    pub fn code(&self, pos: (usize, usize), generation: i32) -> Option<char> {
+        let died_at = self.died_at[(pos.0 % self.size.0) + (pos.1 % self.size.1) * self.size.0];
+        if self.codes.is_empty() || died_at <= generation - self.length {
+            return None;
+        }
+        let age = (generation - died_at) as usize;
+        Some(self.codes[age * self.codes.len() / self.length as usize])
+    }
+}
@@ -0,0 +1,45 @@
+use crate::{
+    engine::*,
+    life_cell::*,
+};
+
+/// Where an engine first disagreed with the reference [`World`].
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    /// Generations run when the states first differed.
+    pub generation: u32,
+    /// The cells that differ, each with whether it is alive in the reference.
+    pub cells: Vec<((usize, usize), bool)>,
+}
+
+/// Runs `start` through the reference [`World`] and the engine made by
+/// `builder` in lockstep for `generations` generations, comparing state hashes
+/// after every generation. Returns the first divergence, if any.
+pub fn verify(start: &World, builder: EngineBuilder, generations: u32) -> Option<Divergence> {
+    let mut reference = start.resized(start.size(), (0, 0));
+    let mut engine = builder(start);
+    for generation in 0..=generations {
+        if generation > 0 {
+            reference.step();
+            engine.step();
+        }
+        if reference.state_hash() != engine.state_hash() {
+            return Some(Divergence { generation, cells: differences(&reference, engine.as_ref()) });
+        }
+    }
+    None
+}
+
+fn differences(reference: &World, engine: &dyn Engine) -> Vec<((usize, usize), bool)> {
+    let size = reference.size();
+    let mut cells = Vec::new();
+    for y in 0..size.1 {
+        for x in 0..size.0 {
+            let alive = Engine::is_alive(reference, (x, y));
+            if alive != engine.is_alive((x, y)) {
+                cells.push(((x, y), alive));
+            }
+        }
+    }
+    cells
+}
@@ -0,0 +1,864 @@
+use std::collections::BTreeMap;
+use bgsp_lib2::{
+    bgsp_common::*,
+    bg_plane::*,
+};
+use crate::{
+    bgchar_data,
+    bgpal_data,
+    cli::{self, *},
+    command::*,
+    condition::*,
+    config::*,
+    console::*,
+    game_window::*,
+    input_role::*,
+    json::*,
+    life_cell::*,
+    multi_state::*,
+    pattern::{self, *},
+    pattern_library::*,
+    remote::*,
+    rule_file::*,
+    session::*,
+    soup::*,
+    trail::*,
+    wait_and_update,
+};
+
+const BG0_RECT_SIZE: (i32, i32) = (128, 120);
+const SESSION_PATH: &str = "life-rs.session";
+const FRAMES_PER_SECOND: i32 = 60;
+const AUTOSAVE_INTERVAL: i32 = 60 * FRAMES_PER_SECOND;
+const MAX_SPAN_LOG2: u32 = 16;
+// how long a goto or a step may run each frame, leaving the rest of the frame for drawing
+const GOTO_FRAME_TIME: std::time::Duration = std::time::Duration::from_millis(12);
+
+enum Prompt {
+    RestoreAutosave(Box<Session>),
+    Quit,
+}
+
+/// Opens the window on `world`, or on `resume` if given, and runs it until
+/// the user quits. Fails if the rule file can't be loaded or the remote
+/// control can't listen.
+pub fn run(args: &Args, config: &Config, mut world: World, resume: Option<Session>) -> Result<(), String> {
+    let world_size = world.size();
+    let vm_rect_size = args.vm_rect_size;
+    // the rule file `states` was loaded from, recorded in sessions so they resume under it
+    let mut rule_file_path = resume.as_ref().map_or_else(|| args.rule_file.clone(), |session| session.rule_file.clone());
+    // with a rule file the grid runs the simulation and the world follows its non-empty cells
+    let mut states = rule_file_path.as_ref().map(|path| load_states(path, world_size)).transpose()?;
+    // a session to switch to at the top of the main loop, and whether it still needs saving
+    let mut pending_session = resume.map(|session| (session, false));
+    let mut lives = world.live_cells().count() as i32;
+
+    let mut remote = args.listen.as_ref().map(Remote::bind).transpose().map_err(|e| format!("--listen: {}", e))?;
+
+    let mut prompt = None;
+    if pending_session.is_none() {
+        if let Some(path) = autosave_path().filter(|path| path.exists()) {
+            match Session::load(&path) {
+                Ok(session) => prompt = Some(Prompt::RestoreAutosave(Box::new(session))),
+                Err(e) => eprintln!("{}: {}", path.display(), e),
+            }
+        }
+    }
+
+    let sdl_context = sdl2::init().unwrap();
+    let video_subsystem = sdl_context.video().unwrap();
+    let mut game_window = GameWindow::new(
+        video_subsystem,
+        args.full_screen,
+        vm_rect_size,
+        args.screen_rotation,
+        args.pixel_scale,
+        args.window_margin,
+    );
+
+    let mut keyboard_map: BTreeMap<piston_window::Key, Vec<_>> = BTreeMap::new();
+    {
+        let key_set_list = config.key_bindings();
+        for key_set in key_set_list {
+            if let Some(role_list) = keyboard_map.get_mut(&key_set.0) {
+                role_list.push(key_set.1);
+            } else {
+                keyboard_map.insert(key_set.0, vec![key_set.1]);
+            }
+        }
+    }
+    let mut input_role_state = InputRoleState::default();
+
+    let mut bg_texture_bank = BgTextureBank::new(
+        &bgchar_data::BG_PATTERN_TBL,
+        &color_tbl(&states),
+        game_window.pixel_scale() as i32,
+    );
+    let rc_bg_texture_bank = Rc::new(RefCell::new(&mut bg_texture_bank));
+    let new_bg1 = |world_size: (usize, usize)| {
+        let mut bg1 = BgPlane::new(
+            (world_size.0 as i32, world_size.1 as i32),
+            vm_rect_size,
+            rc_bg_texture_bank.clone(),
+        );
+        bg1.set_base_symmetry(BgSymmetry::Normal);
+        bg1
+    };
+    let mut bg = {
+        // the text overlay has to cover the whole virtual screen
+        let bg0_rect_size = (
+            BG0_RECT_SIZE.0.max((vm_rect_size.0 + PATTERN_SIZE as i32 - 1) / PATTERN_SIZE as i32),
+            BG0_RECT_SIZE.1.max((vm_rect_size.1 + PATTERN_SIZE as i32 - 1) / PATTERN_SIZE as i32),
+        );
+        let mut bg0 = BgPlane::new(
+            bg0_rect_size,
+            vm_rect_size,
+            rc_bg_texture_bank.clone(),
+        );
+        bg0.set_base_symmetry(BgSymmetry::Normal);
+
+        let bg1 = new_bg1(world_size);
+        (bg0, bg1)
+    };
+
+    if game_window.full_screen() {
+        sdl_context.mouse().show_cursor(false);
+    }
+
+    if let cli::PatternSource::Random(soup) = &args.pattern {
+        bg.0.set_cur_pos(1, 3)
+            .put_string(&format!("Seed:{} {:?} {}", soup.seed, soup.symmetry, soup.density), Some(&CharAttributes::new(4, BgSymmetry::Normal)))
+        ;
+    }
+    if let Some((_, rule_file)) = &states {
+        bg.0.set_cur_pos(1, 4)
+            .put_string(&format!("Rule file:{} ({} states)", rule_file.name, rule_file.engine.num_states()), Some(&CharAttributes::new(4, BgSymmetry::Normal)))
+        ;
+    }
+
+    let mut g_count = 0;
+    // each step runs 2^g_span_log2 generations and only the last is drawn
+    let mut g_span_log2 = 0;
+    let mut renderd = false;
+    let mut wait = 8;
+    let mut pause = true;
+    let mut one_tick = false;
+    let mut diff_mode = false;
+    let mut trail: Option<Trail> = None;
+    let mut symmetry = Symmetry::default();
+    let mut view_pos = BgPos {x:0, y:0};
+    let mut unsaved = false;
+    let mut autosaved = true;
+    let mut library_cursor: Option<usize> = None;
+    let mut console = Console::default();
+    let mut text_input = TextInput::default();
+    // generations run per step, for speeds beyond one generation a frame
+    let mut gens_per_step = 1;
+    // generations of the current step still to run; it's drawn once they're done
+    let mut step_left: u32 = 0;
+    // a goto in progress: the generation it started from, its target and who asked
+    let mut goto: Option<(i32, i32, Option<Request>)> = None;
+    let mut watch = Watch::default();
+    // the condition that last paused the simulation, shown until it runs again
+    let mut stopped: Option<Condition> = None;
+    input_role_state.clear_all();
+    'mail_loop: loop {
+        if let Some((session, restored_unsaved)) = pending_session.take() {
+            let session_states = if session.rule_file == rule_file_path {
+                Ok(states.take())
+            } else {
+                session.rule_file.as_ref().map(|path| load_states(path, world.size())).transpose()
+            };
+            match session_states {
+                Ok(session_states) => {
+                    if session.rule_file != rule_file_path {
+                        reset_color_tbl(&rc_bg_texture_bank, &session_states, game_window.pixel_scale() as i32);
+                        rule_file_path = session.rule_file.clone();
+                    }
+                    states = session_states;
+                    world = session.world();
+                    lives = world.live_cells().count() as i32;
+                    bg.1 = new_bg1(world.size());
+                    if trail.is_some() {
+                        trail = Some(Trail::new(world.size(), args.trail_length, &args.trail_glyphs));
+                    }
+                    g_count = session.generation;
+                    view_pos = BgPos { x: session.view_pos.0, y: session.view_pos.1 };
+                    wait = session.wait;
+                    pause = session.pause;
+                    renderd = false;
+                    unsaved = restored_unsaved;
+                }
+                Err(e) => {
+                    bg.0.set_cur_pos(1, 4)
+                        .put_string(&e, Some(&CharAttributes::new(2, BgSymmetry::Normal)))
+                        .put_code_n(' ', 10)
+                    ;
+                }
+            }
+        }
+        {
+            if input_role_state.get(InputRole::Quit).1 & 0b11 == 0b01 {
+                input_role_state.set_false(InputRole::Quit);
+                // quitting before answering the restore prompt leaves the autosave for next time
+                if !unsaved || matches!(prompt, Some(Prompt::RestoreAutosave(_))) {
+                    break 'mail_loop;
+                }
+                prompt = Some(Prompt::Quit);
+            }
+            if let Some(p) = &prompt {
+                let question = match p {
+                    Prompt::RestoreAutosave(session) => format!("Restore autosave of gen {}? (Y/N)", session.generation),
+                    Prompt::Quit => "Quit without saving? (Y/N)".to_string(),
+                };
+                bg.0.set_cur_pos(1, 6)
+                    .put_string(&question, Some(&CharAttributes::new(2, BgSymmetry::Normal)))
+                ;
+                let answer = if input_role_state.get(InputRole::Yes).1 & 0b1111 == 0b1000 {
+                    Some(true)
+                } else if input_role_state.get(InputRole::No).1 & 0b1111 == 0b1000 {
+                    Some(false)
+                } else {
+                    None
+                };
+                if let Some(answer) = answer {
+                    bg.0.set_cur_pos(1, 6).put_code_n(' ', question.len() as i32);
+                    match prompt.take() {
+                        Some(Prompt::RestoreAutosave(session)) if answer => pending_session = Some((*session, true)),
+                        Some(Prompt::Quit) if answer => break 'mail_loop,
+                        _ => {}
+                    }
+                }
+            }
+        }
+        if input_role_state.get(InputRole::Console).1 & 0b1111 == 0b1000 {
+            console.open();
+            input_role_state.clear_all();
+        }
+        // commands typed into the console, then those from remote clients
+        let mut commands = Vec::new();
+        let console_was_open = console.is_open();
+        if console_was_open {
+            commands.extend(console.feed(&mut text_input).iter().map(|line| (Command::parse(line), None)));
+        }
+        if let Some(remote) = &mut remote {
+            commands.extend(remote.poll().into_iter().map(|(request, command)| (command, Some(request))));
+        }
+        for (command, request) in commands {
+            let command = match command {
+                Ok(Command::Step(generations)) => Ok(Command::Goto(g_count.saturating_add(generations))),
+                command => command,
+            };
+            // a goto runs over the following frames and is answered when it is done
+            if let Ok(Command::Goto(generation)) = command {
+                if generation >= g_count {
+                    if let Some((_, _, previous)) = goto.replace((g_count, generation, request)) {
+                        let result = Err(format!("goto replaced by goto {}", generation));
+                        answer(&mut console, &mut remote, previous, result, status(g_count, lives, pause));
+                    }
+                    // it stops at its own generation, not wherever a step under way would
+                    step_left = 0;
+                    pause = false;
+                    continue;
+                }
+            }
+            // a message for the console and the extra members of a remote response
+            let result: Result<(String, Vec<(String, Json)>), String> = command.and_then(|command| match command {
+                Command::Help => Ok((
+                    COMMANDS.iter().map(|(_, usage)| *usage).collect::<Vec<_>>().join("\n"),
+                    vec![("commands".to_string(), Json::Array(COMMANDS.iter().map(|(name, _)| (*name).into()).collect()))],
+                )),
+                Command::Rule(rule) => {
+                    world.set_rule(rule);
+                    if states.take().is_some() {
+                        reset_color_tbl(&rc_bg_texture_bank, &states, game_window.pixel_scale() as i32);
+                    }
+                    rule_file_path = None;
+                    (unsaved, autosaved) = (true, false);
+                    Ok((format!("Rule {}", rule), vec![("rule".to_string(), rule.to_string().into())]))
+                }
+                Command::Goto(_) => Err(format!("already at generation {}", g_count)),
+                Command::Load(name, text) => {
+                    let pattern = match text {
+                        Some(text) => pattern::parse(&text),
+                        None => match find(&name) {
+                            Some(entry) if !std::path::Path::new(&name).exists() => Ok(entry.pattern()),
+                            _ => pattern::load(&name),
+                        },
+                    };
+                    let pattern = pattern.and_then(|pattern| pattern.check_fits(world.size()).map(|_| pattern))
+                        .map_err(|e| format!("{}: {}", name, e))?;
+                    let world_size = world.size();
+                    let rule = pattern.rule.unwrap_or(world.rule());
+                    world = World::new(world_size);
+                    world.set_rule(rule);
+                    lives = pattern.place(&mut world, (
+                        (world_size.0 - pattern.size.0) / 2,
+                        (world_size.1 - pattern.size.1) / 2,
+                    ));
+                    g_count = 0;
+                    if trail.is_some() {
+                        trail = Some(Trail::new(world_size, args.trail_length, &args.trail_glyphs));
+                    }
+                    (unsaved, autosaved) = (true, false);
+                    renderd = false;
+                    Ok((format!("Loaded {} ({})", name, rule), vec![("rule".to_string(), rule.to_string().into())]))
+                }
+                Command::Clear => {
+                    let rule = world.rule();
+                    world = World::new(world.size());
+                    world.set_rule(rule);
+                    lives = 0;
+                    g_count = 0;
+                    if trail.is_some() {
+                        trail = Some(Trail::new(world.size(), args.trail_length, &args.trail_glyphs));
+                    }
+                    (unsaved, autosaved) = (true, false);
+                    renderd = false;
+                    Ok((String::new(), Vec::new()))
+                }
+                Command::Random(density, seed) => {
+                    let seed = seed.unwrap_or_else(|| std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map_or(0, |d| d.as_nanos() as u64));
+                    let soup = Soup::new(seed, density, symmetry);
+                    let world_size = world.size();
+                    lives += soup.fill(&mut world, ((0, 0), world_size));
+                    (unsaved, autosaved) = (true, false);
+                    renderd = false;
+                    Ok((
+                        format!("Seed:{} {:?} {}", soup.seed, soup.symmetry, soup.density),
+                        vec![("seed".to_string(), Json::String(soup.seed.to_string()))],
+                    ))
+                }
+                Command::Speed(speed) => {
+                    // steps happen on whole frames, so the rate is rounded to the nearest one they allow
+                    (wait, gens_per_step) = if speed <= FRAMES_PER_SECOND {
+                        ((FRAMES_PER_SECOND + speed / 2) / speed, 1)
+                    } else {
+                        (1, (speed + FRAMES_PER_SECOND / 2) / FRAMES_PER_SECOND)
+                    };
+                    pause = false;
+                    let rate = FRAMES_PER_SECOND as f64 / wait as f64 * gens_per_step as f64;
+                    let message = if rate == speed as f64 {
+                        format!("{} generations per second", speed)
+                    } else {
+                        format!("{} generations per second (rounded from {})", (rate * 100.0).round() / 100.0, speed)
+                    };
+                    Ok((message, vec![("speed".to_string(), Json::Number(rate))]))
+                }
+                Command::Pause => {
+                    pause = true;
+                    Ok(("Paused".to_string(), Vec::new()))
+                }
+                Command::Run => {
+                    pause = false;
+                    Ok(("Running".to_string(), Vec::new()))
+                }
+                Command::Save(path) => {
+                    let mut pattern = Pattern::from_world(&world, None);
+                    pattern.comments.push(format!("Generation: {}", g_count));
+                    pattern::save(&pattern, &path).map_err(|e| format!("{}: {}", path, e))?;
+                    Ok((format!("Saved {}", path), Vec::new()))
+                }
+                Command::Population => Ok((format!("Gen:{} Lives:{}", g_count, lives), Vec::new())),
+                Command::Region(pos, size) => {
+                    let world_size = world.size();
+                    if pos.0.saturating_add(size.0) > world_size.0 || pos.1.saturating_add(size.1) > world_size.1 {
+                        return Err(format!("the region lies outside the {}x{} world", world_size.0, world_size.1));
+                    }
+                    let mut cells = Vec::new();
+                    for y in pos.1..pos.1 + size.1 {
+                        for x in pos.0..pos.0 + size.0 {
+                            if world.read_cell((x, y)).state == CellState::Live {
+                                cells.push(Json::Array(vec![x.into(), y.into()]));
+                            }
+                        }
+                    }
+                    Ok((format!("{} live cells", cells.len()), vec![("cells".to_string(), Json::Array(cells))]))
+                }
+                Command::SetCells(cells, alive) => {
+                    let world_size = world.size();
+                    if let Some((x, y)) = cells.iter().find(|(x, y)| *x >= world_size.0 || *y >= world_size.1) {
+                        return Err(format!("{},{} lies outside the {}x{} world", x, y, world_size.0, world_size.1));
+                    }
+                    let state = if alive { CellState::Live } else { CellState::Dead };
+                    for &pos in &cells {
+                        lives += world.set_cell(pos, state);
+                    }
+                    (unsaved, autosaved) = (true, false);
+                    renderd = false;
+                    Ok((format!("Set {} cells", cells.len()), Vec::new()))
+                }
+                Command::Step(_) => unreachable!(),
+                Command::Until(condition) => {
+                    let world_size = world.size();
+                    if let Condition::RegionChanged(pos, size) = condition {
+                        if pos.0.saturating_add(size.0) > world_size.0 || pos.1.saturating_add(size.1) > world_size.1 {
+                            return Err(format!("the region lies outside the {}x{} world", world_size.0, world_size.1));
+                        }
+                    }
+                    watch.add(condition);
+                    Ok((format!("Until {}", condition), vec![conditions(&watch)]))
+                }
+                Command::Conditions if watch.conditions().is_empty() => Ok(("No conditions".to_string(), vec![conditions(&watch)])),
+                Command::Conditions => Ok((
+                    watch.conditions().iter().map(|condition| format!("Until {}", condition)).collect::<Vec<_>>().join("\n"),
+                    vec![conditions(&watch)],
+                )),
+                Command::ClearConditions => {
+                    watch.clear();
+                    Ok(("Conditions cleared".to_string(), vec![conditions(&watch)]))
+                }
+                Command::View(x, y) => {
+                    view_pos = BgPos { x: x.saturating_mul(PATTERN_SIZE as i32), y: y.saturating_mul(PATTERN_SIZE as i32) };
+                    Ok((format!("View {},{}", x, y), Vec::new()))
+                }
+            });
+            answer(&mut console, &mut remote, request, result, status(g_count, lives, pause));
+        }
+        if console_was_open {
+            if console.is_open() {
+                console.draw(&mut bg.0, vm_rect_size);
+            } else {
+                console.clear(&mut bg.0, vm_rect_size);
+                input_role_state.clear_all();
+            }
+        }
+        if input_role_state.get(InputRole::Library).1 & 0b1111 == 0b1000 {
+            library_cursor = if library_cursor.is_none() { Some(0) } else { None };
+            for idx in 0..LIBRARY.len() {
+                bg.0.set_cur_pos(1, 8 + idx as i32).put_code_n(' ', 32);
+            }
+        }
+        if let Some(cursor) = &mut library_cursor {
+            if input_role_state.get(InputRole::Up).1 & 0b11 == 0b01 {
+                *cursor = (*cursor + LIBRARY.len() - 1) % LIBRARY.len();
+            }
+            if input_role_state.get(InputRole::Down).1 & 0b11 == 0b01 {
+                *cursor = (*cursor + 1) % LIBRARY.len();
+            }
+            for (idx, entry) in LIBRARY.iter().enumerate() {
+                let (mark, palette) = if idx == *cursor { ('>', 4) } else { (' ', 3) };
+                bg.0.set_cur_pos(1, 8 + idx as i32)
+                    .put_string(&format!("{} {}", mark, entry.name), Some(&CharAttributes::new(palette, BgSymmetry::Normal)))
+                ;
+            }
+            if input_role_state.get(InputRole::Button0).1 & 0b1111 == 0b1000 {
+                let pattern = LIBRARY[*cursor].pattern();
+                let world_size = world.size();
+                let (view_pos, view_size) = view_rect(&view_pos, world_size, vm_rect_size);
+                let centre = (view_pos.0 + view_size.0 / 2, view_pos.1 + view_size.1 / 2);
+                let pos = (
+                    (centre.0 as isize - (pattern.size.0 / 2) as isize).rem_euclid(world_size.0 as isize) as usize,
+                    (centre.1 as isize - (pattern.size.1 / 2) as isize).rem_euclid(world_size.1 as isize) as usize,
+                );
+                lives += pattern.place(&mut world, pos);
+                (unsaved, autosaved) = (true, false);
+                renderd = false;
+            }
+        }
+        {
+            let d = if input_role_state.get(InputRole::Button0).0 { 6 } else { 2 };
+            if library_cursor.is_none() {
+                if input_role_state.get(InputRole::Left).0 {
+                    view_pos.x -= d;
+                }
+                if input_role_state.get(InputRole::Right).0 {
+                    view_pos.x += d;
+                }
+                if input_role_state.get(InputRole::Up).0 {
+                    view_pos.y -= d;
+                }
+                if input_role_state.get(InputRole::Down).0 {
+                    view_pos.y += d;
+                }
+            }
+            if input_role_state.get(InputRole::Home).1 & 0b1111 == 0b1000 {
+                view_pos.x = 0;
+                view_pos.y = 0;
+            }
+            if input_role_state.get(InputRole::Progress1).1 & 0b1111 == 0b1000 {
+                (wait, gens_per_step) = (8, 1);
+                pause = false;
+            }
+            if input_role_state.get(InputRole::Progress2).1 & 0b1111 == 0b1000 {
+                (wait, gens_per_step) = (4, 1);
+                pause = false;
+            }
+            if input_role_state.get(InputRole::Progress4).1 & 0b1111 == 0b1000 {
+                (wait, gens_per_step) = (2, 1);
+                pause = false;
+            }
+            if input_role_state.get(InputRole::Progress8).1 & 0b1111 == 0b1000 {
+                (wait, gens_per_step) = (1, 1);
+                pause = false;
+            }
+            if input_role_state.get(InputRole::SpanUp).1 & 0b1111 == 0b1000 {
+                g_span_log2 = (g_span_log2 + 1).min(MAX_SPAN_LOG2);
+                renderd = false;
+            }
+            if input_role_state.get(InputRole::SpanDown).1 & 0b1111 == 0b1000 {
+                g_span_log2 = g_span_log2.saturating_sub(1);
+                renderd = false;
+            }
+            if input_role_state.get(InputRole::Pause).1 & 0b1111 == 0b1000 {
+                pause = !pause;
+            }
+            if input_role_state.get(InputRole::OneTick).1 & 0b1111 == 0b1000 {
+                one_tick = true;
+            }
+            if input_role_state.get(InputRole::DiffMode).1 & 0b1111 == 0b1000 {
+                diff_mode = !diff_mode;
+                renderd = false;
+            }
+            if input_role_state.get(InputRole::TrailMode).1 & 0b1111 == 0b1000 {
+                trail = if trail.is_none() {
+                    Some(Trail::new(world.size(), args.trail_length, &args.trail_glyphs))
+                } else {
+                    None
+                };
+                renderd = false;
+            }
+            let resize = {
+                let world_size = world.size();
+                let bounding_box = world.bounding_box();
+                let centre_bounding_box = |size: (usize, usize)| match bounding_box {
+                    Some((pos, bb_size)) => (
+                        (size.0 - bb_size.0) as isize / 2 - pos.0 as isize,
+                        (size.1 - bb_size.1) as isize / 2 - pos.1 as isize,
+                    ),
+                    None => (0, 0),
+                };
+                if input_role_state.get(InputRole::Grow).1 & 0b1111 == 0b1000 {
+                    let size = ((world_size.0 * 2).min(MAX_WORLD_SIZE), (world_size.1 * 2).min(MAX_WORLD_SIZE));
+                    Some((size, ((size.0 - world_size.0) as isize / 2, (size.1 - world_size.1) as isize / 2)))
+                } else if input_role_state.get(InputRole::Shrink).1 & 0b1111 == 0b1000 {
+                    let min_size = bounding_box.map_or(MIN_WORLD_SIZE, |(_, bb_size)| bb_size);
+                    let size = (
+                        (world_size.0 / 2).max(min_size.0).max(MIN_WORLD_SIZE.0),
+                        (world_size.1 / 2).max(min_size.1).max(MIN_WORLD_SIZE.1),
+                    );
+                    Some((size, centre_bounding_box(size)))
+                } else if input_role_state.get(InputRole::Crop).1 & 0b1111 == 0b1000 {
+                    bounding_box.map(|(_, bb_size)| {
+                        let size = (bb_size.0.max(MIN_WORLD_SIZE.0), bb_size.1.max(MIN_WORLD_SIZE.1));
+                        (size, centre_bounding_box(size))
+                    })
+                } else {
+                    None
+                }
+            };
+            if input_role_state.get(InputRole::Symmetry).1 & 0b1111 == 0b1000 {
+                symmetry = symmetry.next();
+                bg.0.set_cur_pos(1, 3)
+                    .put_string(&format!("Symmetry:{:?}", symmetry), Some(&CharAttributes::new(4, BgSymmetry::Normal)))
+                    .put_code_n(' ', 30)
+                ;
+            }
+            let random_rect = {
+                let world_size = world.size();
+                if input_role_state.get(InputRole::RandomWorld).1 & 0b1111 == 0b1000 {
+                    Some(((0, 0), world_size))
+                } else if input_role_state.get(InputRole::RandomView).1 & 0b1111 == 0b1000 {
+                    Some(view_rect(&view_pos, world_size, vm_rect_size))
+                } else {
+                    None
+                }
+            };
+            if let Some(rect) = random_rect {
+                let seed = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |d| d.as_nanos() as u64);
+                let soup = Soup::new(seed, RANDOM_DENSITY, symmetry);
+                lives += soup.fill(&mut world, rect);
+                (unsaved, autosaved) = (true, false);
+                bg.0.set_cur_pos(1, 3)
+                    .put_string(&format!("Seed:{} {:?} {}", soup.seed, soup.symmetry, soup.density), Some(&CharAttributes::new(4, BgSymmetry::Normal)))
+                    .put_code_n(' ', 4)
+                ;
+                renderd = false;
+            }
+            if input_role_state.get(InputRole::Export).1 & 0b1111 == 0b1000 {
+                let region = if input_role_state.get(InputRole::Button0).0 {
+                    Some(view_rect(&view_pos, world.size(), vm_rect_size))
+                } else {
+                    None
+                };
+                let mut pattern = Pattern::from_world(&world, region);
+                pattern.comments.push(format!("Generation: {}", g_count));
+                let path = format!("life-{}.rle", g_count);
+                let message = match std::fs::write(&path, pattern::write(&pattern, Format::Rle)) {
+                    Ok(()) => format!("Saved {}", path),
+                    Err(e) => format!("{}: {}", path, e),
+                };
+                bg.0.set_cur_pos(1, 4)
+                    .put_string(&message, Some(&CharAttributes::new(4, BgSymmetry::Normal)))
+                    .put_code_n(' ', 10)
+                ;
+            }
+            if input_role_state.get(InputRole::SaveSession).1 & 0b1111 == 0b1000 {
+                let session = Session::capture(&world, rule_file_path.as_deref(), g_count, (view_pos.x, view_pos.y), wait, pause);
+                let message = match session.save(SESSION_PATH) {
+                    Ok(()) => {
+                        unsaved = false;
+                        format!("Saved {}", SESSION_PATH)
+                    }
+                    Err(e) => format!("{}: {}", SESSION_PATH, e),
+                };
+                bg.0.set_cur_pos(1, 4)
+                    .put_string(&message, Some(&CharAttributes::new(4, BgSymmetry::Normal)))
+                    .put_code_n(' ', 10)
+                ;
+            }
+            if input_role_state.get(InputRole::LoadSession).1 & 0b1111 == 0b1000 {
+                match Session::load(SESSION_PATH) {
+                    Ok(session) => pending_session = Some((session, false)),
+                    Err(e) => {
+                        bg.0.set_cur_pos(1, 4)
+                            .put_string(&format!("{}: {}", SESSION_PATH, e), Some(&CharAttributes::new(2, BgSymmetry::Normal)))
+                            .put_code_n(' ', 10)
+                        ;
+                    }
+                }
+            }
+            if let Some((size, offset)) = resize {
+                world = world.resized(size, offset);
+                (unsaved, autosaved) = (true, false);
+                lives = world.live_cells().count() as i32;
+                bg.1 = new_bg1(size);
+                if trail.is_some() {
+                    trail = Some(Trail::new(size, args.trail_length, &args.trail_glyphs));
+                }
+                view_pos.x += offset.0 as i32 * PATTERN_SIZE as i32;
+                view_pos.y += offset.1 as i32 * PATTERN_SIZE as i32;
+                renderd = false;
+            }
+            bg.1.set_view_pos(view_pos.x, view_pos.y);
+        }
+        if !renderd {
+            let world_size = world.size();
+            for y in 0..world_size.1 {
+                for x in 0..world_size.0 {
+                    let cell = world.read_cell((x, y));
+                    let (code, palette) = if cell.state == CellState::Live {
+                        ('*', states.as_ref().map_or(1, |(grid, _)| bgpal_data::STATE_PALETTE_BASE as u32 + grid.read_cell((x, y)) as u32))
+                    } else if let Some(code) = trail.as_ref().and_then(|t| t.code((x, y), g_count)) {
+                        (code, 5)
+                    } else {
+                        (' ', 1)
+                    };
+                    bg.1.set_cur_pos(x as i32, y as i32)
+                        .put_code(code)
+                        .put_palette(palette)
+                    ;
+                }
+            }
+            if diff_mode {
+                for (x, y) in world.changes() {
+                    let cell = world.read_cell((x, y));
+                    bg.1.set_cur_pos(x as i32, y as i32)
+                        .put_code('*')
+                        .put_palette(if cell.state == CellState::Live { 3 } else { 2 })
+                    ;
+                }
+            }
+            let stopped_at = stopped.map_or(String::new(), |condition| format!("Stopped: until {}", condition));
+            bg.0.set_cur_pos(1, 2)
+                .put_string(&format!("Gen:{} Lives:{} Step:{}  {}", &g_count, &lives, 1 << g_span_log2, stopped_at), Some(&CharAttributes::new(2, BgSymmetry::Normal)))
+                .put_code_n(' ', 40)
+            ;
+            renderd = true;
+        }
+        // the condition that stopped the simulation this frame
+        let mut fired = None;
+        if let Some((from, target, _)) = goto {
+            stopped = None;
+            // runs without drawing until the target, or until paused
+            let start = std::time::Instant::now();
+            while fired.is_none() && !pause && g_count < target && start.elapsed() < GOTO_FRAME_TIME {
+                lives += step_world(&mut world, &mut states);
+                g_count += 1;
+                if let Some(trail) = &mut trail {
+                    trail.record(&world, g_count);
+                }
+                fired = watch.check(&world, g_count, lives as usize);
+            }
+            if fired.is_some() {
+                pause = true;
+            }
+            // generations run since the last save are as much at risk as edits
+            (unsaved, autosaved) = (true, false);
+            let progress = format!("Goto {}: gen {} ({}%)", target, g_count,
+                (g_count - from).max(0) as i64 * 100 / (target - from).max(1) as i64);
+            bg.0.set_cur_pos(1, 5)
+                .put_string(&progress, Some(&CharAttributes::new(4, BgSymmetry::Normal)))
+                .put_code_n(' ', 4)
+            ;
+            if pause || g_count >= target {
+                let (_, _, request) = goto.take().unwrap();
+                let result = match fired {
+                    Some(condition) => Err(format!("goto stopped at generation {} by until {}", g_count, condition)),
+                    None if g_count >= target => Ok((format!("Generation {}", g_count), Vec::new())),
+                    None => Err(format!("goto stopped at generation {}", g_count)),
+                };
+                pause = true;
+                answer(&mut console, &mut remote, request, result, status(g_count, lives, pause));
+                if console.is_open() {
+                    console.draw(&mut bg.0, vm_rect_size);
+                }
+                bg.0.set_cur_pos(1, 5).put_code_n(' ', progress.len() as i32 + 4);
+                renderd = false;
+            }
+        } else if step_left > 0 || one_tick || !pause && game_window.f_count() % wait == 0 {
+            // like goto, a step that doesn't fit in the frame carries on over
+            // the next frames without drawing
+            stopped = None;
+            if step_left == 0 {
+                step_left = (1u32 << g_span_log2).saturating_mul(gens_per_step as u32);
+            }
+            let start = std::time::Instant::now();
+            while step_left > 0 && start.elapsed() < GOTO_FRAME_TIME {
+                lives += step_world(&mut world, &mut states);
+                g_count += 1;
+                step_left -= 1;
+                if let Some(trail) = &mut trail {
+                    trail.record(&world, g_count);
+                }
+                fired = watch.check(&world, g_count, lives as usize);
+                if fired.is_some() {
+                    pause = true;
+                    step_left = 0;
+                }
+            }
+            (unsaved, autosaved) = (true, false);
+            if step_left == 0 {
+                renderd = false;
+                if one_tick {
+                    one_tick = false;
+                    pause = true;
+                }
+            }
+        }
+        if fired.is_some() {
+            stopped = fired;
+            renderd = false;
+        }
+        bg.0.set_cur_pos(1, 1)
+            .put_string(&format!("({}, {})", view_pos.x, view_pos.y), Some(&CharAttributes::new(3, BgSymmetry::Normal)))
+            .put_code_n(' ', 10)
+        ;
+        // the autosave on disk may be the one the restore prompt is asking about
+        let restore_pending = matches!(prompt, Some(Prompt::RestoreAutosave(_)));
+        if !autosaved && !restore_pending && game_window.f_count() % AUTOSAVE_INTERVAL == 0 {
+            if let Some(path) = autosave_path() {
+                match Session::capture(&world, rule_file_path.as_deref(), g_count, (view_pos.x, view_pos.y), wait, pause).save(&path) {
+                    Ok(()) => autosaved = true,
+                    Err(e) => eprintln!("{}: {}", path.display(), e),
+                }
+            }
+        }
+        let text_input = if console.is_open() { Some(&mut text_input) } else { None };
+        if wait_and_update::doing(&mut game_window, &mut bg, &keyboard_map, &mut input_role_state, text_input) {
+             break 'mail_loop;
+        }
+    }
+    if let Some(path) = autosave_path().filter(|_| !matches!(prompt, Some(Prompt::RestoreAutosave(_)))) {
+        if unsaved {
+            if let Err(e) = Session::capture(&world, rule_file_path.as_deref(), g_count, (view_pos.x, view_pos.y), wait, pause).save(&path) {
+                eprintln!("{}: {}", path.display(), e);
+            }
+        } else if path.exists() {
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+    sdl_context.mouse().show_cursor(true);
+    Ok(())
+}
+
+// the members every successful remote response starts with
+fn status(generation: i32, population: i32, paused: bool) -> Vec<(String, Json)> {
+    vec![
+        ("ok".to_string(), true.into()),
+        ("generation".to_string(), generation.into()),
+        ("population".to_string(), population.into()),
+        ("paused".to_string(), paused.into()),
+    ]
+}
+
+fn conditions(watch: &Watch) -> (String, Json) {
+    ("conditions".to_string(), Json::Array(watch.conditions().iter().map(|condition| condition.to_string().into()).collect()))
+}
+
+// reports the result of a command on the console, or to the remote client that sent it
+fn answer(
+    console: &mut Console,
+    remote: &mut Option<Remote>,
+    request: Option<Request>,
+    result: Result<(String, Vec<(String, Json)>), String>,
+    status: Vec<(String, Json)>,
+) {
+    match request {
+        None => match result {
+            Ok((message, _)) => for line in message.lines() {
+                console.print(line, 3);
+            },
+            Err(e) => console.print(e, 2),
+        },
+        Some(request) => {
+            let response = match result {
+                Ok((_, fields)) => status.into_iter().chain(fields).collect(),
+                Err(e) => vec![("ok".to_string(), false.into()), ("error".to_string(), e.into())],
+            };
+            if let Some(remote) = remote {
+                remote.reply(&request, response);
+            }
+        }
+    }
+}
+
+fn view_rect(view_pos: &BgPos, world_size: (usize, usize), vm_rect_size: (i32, i32)) -> ((usize, usize), (usize, usize)) {
+    let pos = (
+        view_pos.x.div_euclid(PATTERN_SIZE as i32).rem_euclid(world_size.0 as i32) as usize,
+        view_pos.y.div_euclid(PATTERN_SIZE as i32).rem_euclid(world_size.1 as i32) as usize,
+    );
+    let size = (
+        (vm_rect_size.0 as usize / PATTERN_SIZE).min(world_size.0),
+        (vm_rect_size.1 as usize / PATTERN_SIZE).min(world_size.1),
+    );
+    (pos, size)
+}
+
+// loads the rule file at `path` with an empty grid for it to run on
+fn load_states(path: &str, size: (usize, usize)) -> Result<(StateGrid, RuleFile), String> {
+    RuleFile::load(path).map(|rule_file| (StateGrid::new(size), rule_file)).map_err(|e| format!("{}: {}", path, e))
+}
+
+// the palettes for `states`: the rule file's state colours, or just the usual ones
+fn color_tbl(states: &Option<(StateGrid, RuleFile)>) -> Box<[[Rgba<u8>; NUM_PALETTE_COL]; NUM_PALETTE_TBL]> {
+    match states {
+        Some((_, rule_file)) => bgpal_data::color_tbl_with_states(rule_file.engine.num_states(), &rule_file.colors),
+        None => Box::new(bgpal_data::COLOR_TBL),
+    }
+}
+
+// rebuilds the texture bank when the rule file changes, so its colours don't outlive it
+fn reset_color_tbl(bank: &RefCell<&mut BgTextureBank>, states: &Option<(StateGrid, RuleFile)>, pixel_scale: i32) {
+    **bank.borrow_mut() = BgTextureBank::new(&bgchar_data::BG_PATTERN_TBL, &color_tbl(states), pixel_scale);
+}
+
+// advances the world one generation, under the rule file if one is running
+fn step_world(world: &mut World, states: &mut Option<(StateGrid, RuleFile)>) -> i32 {
+    match states {
+        Some((grid, rule_file)) => {
+            // the world may have been resized or edited since the last generation
+            if grid.size() != world.size() {
+                *grid = StateGrid::new(world.size());
+            }
+            grid.follow(world);
+            grid.update(&rule_file.engine);
+            world.follow(grid)
+        }
+        None => world.update_world(),
+    }
+}
@@ -1,7 +1,8 @@
 use crate::{
+    console::*,
     direction::*,
     input_role::*,
-    GameWindow,
+    game_window::GameWindow,
 };
 use std::collections::BTreeMap;
 use piston_window::*;
@@ -16,6 +17,7 @@ pub fn doing(
     bg: &mut (BgPlane, BgPlane),
     keyboard_map: &BTreeMap<piston_window::Key, Vec<InputRole>>,
     input_role_state: &mut InputRoleState,
+    mut text_input: Option<&mut TextInput>,
 ) -> bool {
     let mut texture_context = game_window.mut_window().create_texture_context();
     let texture_settings = TextureSettings::new();
@@ -42,15 +44,28 @@ pub fn doing(
 
     while let Some(event) = game_window.mut_window().next() {
         if let Some(Button::Keyboard(k)) = event.press_args() {
-            if let Some(role_list) = keyboard_map.get(&k) {
+            // while typing, keys go to the text and not to their roles
+            if let Some(input) = &mut text_input {
+                input.keys.push(k);
+            } else if let Some(role_list) = keyboard_map.get(&k) {
                 for role in role_list { input_role_state.set_true(*role); }
             }
         }
+        if let Some(text) = event.text_args() {
+            if let Some(input) = &mut text_input {
+                input.text.push_str(&text);
+            }
+        }
         if let Some(Button::Keyboard(k)) = event.release_args() {
             if let Some(role_list) = keyboard_map.get(&k) {
                 for role in role_list { input_role_state.set_false(*role); }
             }
         }
+        if event.close_args().is_some() {
+            // leave it to the main loop to confirm quitting with unsaved edits
+            game_window.mut_window().set_should_close(false);
+            input_role_state.set_true(InputRole::Quit);
+        }
         if let Event::Loop(Loop::Render(_)) = event {
             input_role_state.update_history();
             let vm_rect_size = game_window.vm_rect_size();